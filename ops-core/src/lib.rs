@@ -1,3 +1,6 @@
+mod tcp;
+
+pub use crate::tcp::TcpChecker;
 pub use async_trait::async_trait;
 
 /// An interface for something that can be periodically checked.
@@ -70,15 +73,20 @@ impl CheckResponse {
 
 /// Health statuses.
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Health {
     Healthy,
     Degraded,
     Unhealthy,
 }
 
-impl Into<&'static str> for Health {
-    fn into(self) -> &'static str {
-        match self {
+impl From<Health> for &'static str {
+    fn from(health: Health) -> Self {
+        match health {
             Health::Healthy => "healthy",
             Health::Degraded => "degraded",
             Health::Unhealthy => "unhealthy",
@@ -0,0 +1,273 @@
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{async_trait, CheckResponse, Checker};
+
+use futures_channel::oneshot;
+
+type Connected = Result<(SocketAddr, Duration), String>;
+
+/// Checks that a TCP connection can be opened to an address.
+///
+/// Resolving and connecting block, so they run on a thread, which checks that overlap share
+/// rather than each starting their own. Resolving has no timeout, so a stuck thread is never
+/// joined by another.
+#[derive(Debug)]
+pub struct TcpChecker {
+    address: String,
+    // The checks waiting on the running attempt, `None` when none is running
+    waiting: Arc<Mutex<Option<Vec<oneshot::Sender<Connected>>>>>,
+    connect_timeout: Duration,
+    degraded_latency: Option<Duration>,
+    unhealthy_latency: Option<Duration>,
+    action: String,
+    impact: String,
+}
+
+impl TcpChecker {
+    /// Creates a [`TcpChecker`](struct.TcpChecker.html) for a `host:port` address, with a
+    /// connect timeout of 5 seconds.
+    pub fn new(address: &str) -> Self {
+        TcpChecker {
+            address: address.to_owned(),
+            waiting: Arc::new(Mutex::new(None)),
+            connect_timeout: Duration::from_secs(5),
+            degraded_latency: None,
+            unhealthy_latency: None,
+            action: format!("Check that {} is reachable", address),
+            impact: format!("Connections to {} cannot be made", address),
+        }
+    }
+
+    /// Sets the timeout for connecting to each resolved address.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Reports the check as degraded when connecting takes at least this long.
+    pub fn degraded_latency(mut self, latency: Duration) -> Self {
+        self.degraded_latency = Some(latency);
+        self
+    }
+
+    /// Reports the check as unhealthy when connecting takes at least this long.
+    pub fn unhealthy_latency(mut self, latency: Duration) -> Self {
+        self.unhealthy_latency = Some(latency);
+        self
+    }
+
+    /// Sets the action reported when the check is not healthy.
+    pub fn action(mut self, action: &str) -> Self {
+        self.action = action.to_owned();
+        self
+    }
+
+    /// Sets the impact reported when the check is unhealthy.
+    pub fn impact(mut self, impact: &str) -> Self {
+        self.impact = impact.to_owned();
+        self
+    }
+}
+
+#[async_trait]
+impl Checker for TcpChecker {
+    async fn check(&self) -> CheckResponse {
+        let (tx, rx) = oneshot::channel();
+
+        let start = {
+            let mut waiting = self.waiting.lock().unwrap_or_else(PoisonError::into_inner);
+
+            match &mut *waiting {
+                Some(waiters) => {
+                    // Checks that timed out while waiting have no use for the result
+                    waiters.retain(|waiter| !waiter.is_canceled());
+                    waiters.push(tx);
+                    false
+                }
+                None => {
+                    *waiting = Some(vec![tx]);
+                    true
+                }
+            }
+        };
+
+        if start {
+            let address = self.address.clone();
+            let timeout = self.connect_timeout;
+            let waiting = self.waiting.clone();
+
+            // Resolving and connecting block, so they are kept off the async runtime
+            thread::spawn(move || {
+                let connected = connect(&address, timeout).map_err(|err| err.to_string());
+
+                let waiters = waiting
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .take()
+                    .unwrap_or_default();
+
+                for waiter in waiters {
+                    let _ = waiter.send(connected.clone());
+                }
+            });
+        }
+
+        let (addr, latency) = match rx.await {
+            Ok(Ok(connected)) => connected,
+            Ok(Err(err)) => {
+                return CheckResponse::unhealthy(
+                    &format!("failed to connect to {}: {}", self.address, err),
+                    &self.action,
+                    &self.impact,
+                )
+            }
+            Err(_) => {
+                return CheckResponse::unhealthy(
+                    &format!("failed to connect to {}: connect was aborted", self.address),
+                    &self.action,
+                    &self.impact,
+                )
+            }
+        };
+
+        let output = format!(
+            "connected to {} ({}) in {:.3}ms",
+            addr,
+            self.address,
+            latency.as_secs_f64() * 1000.0
+        );
+
+        if self.unhealthy_latency.map_or(false, |max| latency >= max) {
+            CheckResponse::unhealthy(&output, &self.action, &self.impact)
+        } else if self.degraded_latency.map_or(false, |max| latency >= max) {
+            CheckResponse::degraded(&output, &self.action)
+        } else {
+            CheckResponse::healthy(&output)
+        }
+    }
+}
+
+/// Connects to the first reachable resolved address, returning it with the connect time.
+fn connect(address: &str, timeout: Duration) -> io::Result<(SocketAddr, Duration)> {
+    let mut last_err = None;
+
+    for addr in address.to_socket_addrs()? {
+        let start = Instant::now();
+
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok((addr, start.elapsed())),
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    Err(last_err
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address did not resolve")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use super::TcpChecker;
+    use crate::{Checker, Health};
+
+    fn listen() -> (TcpListener, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    #[tokio::test]
+    async fn connects() {
+        let (_listener, port) = listen();
+
+        let resp = TcpChecker::new(&format!("localhost:{}", port))
+            .check()
+            .await;
+
+        assert_eq!(resp.health(), Health::Healthy);
+        assert!(resp.output().starts_with(&format!(
+            "connected to 127.0.0.1:{} (localhost:{}) in ",
+            port, port
+        )));
+    }
+
+    #[tokio::test]
+    async fn refused_connect_is_unhealthy() {
+        let (listener, port) = listen();
+        drop(listener);
+
+        let address = format!("127.0.0.1:{}", port);
+        let resp = TcpChecker::new(&address)
+            .action("Restart the database")
+            .impact("Orders cannot be taken")
+            .check()
+            .await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp
+            .output()
+            .starts_with(&format!("failed to connect to {}: ", address)));
+        assert_eq!(resp.action(), Some("Restart the database"));
+        assert_eq!(resp.impact(), Some("Orders cannot be taken"));
+    }
+
+    #[tokio::test]
+    async fn default_action_and_impact() {
+        let (listener, port) = listen();
+        drop(listener);
+
+        let address = format!("127.0.0.1:{}", port);
+        let resp = TcpChecker::new(&address).check().await;
+
+        assert_eq!(
+            resp.action(),
+            Some(format!("Check that {} is reachable", address).as_str())
+        );
+        assert_eq!(
+            resp.impact(),
+            Some(format!("Connections to {} cannot be made", address).as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn latency_thresholds() {
+        let (_listener, port) = listen();
+        let address = format!("127.0.0.1:{}", port);
+
+        let checker = TcpChecker::new(&address).degraded_latency(Duration::ZERO);
+        let resp = checker.check().await;
+        assert_eq!(resp.health(), Health::Degraded);
+        assert_eq!(
+            resp.action(),
+            Some(format!("Check that {} is reachable", address).as_str())
+        );
+
+        let checker = TcpChecker::new(&address)
+            .degraded_latency(Duration::ZERO)
+            .unhealthy_latency(Duration::ZERO);
+        assert_eq!(checker.check().await.health(), Health::Unhealthy);
+
+        let checker = TcpChecker::new(&address)
+            .degraded_latency(Duration::from_secs(5))
+            .unhealthy_latency(Duration::from_secs(10));
+        assert_eq!(checker.check().await.health(), Health::Healthy);
+    }
+
+    #[tokio::test]
+    async fn overlapping_checks_share_the_connect() {
+        let (_listener, port) = listen();
+        let checker = TcpChecker::new(&format!("127.0.0.1:{}", port));
+
+        let (first, second) = tokio::join!(checker.check(), checker.check());
+
+        assert_eq!(first.health(), Health::Healthy);
+        assert_eq!(second.health(), Health::Healthy);
+        assert_eq!(first.output(), second.output());
+    }
+}
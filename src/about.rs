@@ -0,0 +1,168 @@
+use crate::build_info::BuildInfo;
+
+use serde::{Deserialize, Serialize};
+
+/// Details of the application, as served on `/__/about`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct About {
+    name: String,
+    description: String,
+    #[serde(default)]
+    links: Vec<Link>,
+    #[serde(default)]
+    owners: Vec<Owner>,
+    #[serde(default, rename = "build-info")]
+    build_info: BuildInfo,
+}
+
+impl About {
+    pub(crate) fn new(name: &str, description: &str) -> Self {
+        About {
+            name: name.to_owned(),
+            description: description.to_owned(),
+            links: Vec::new(),
+            owners: Vec::new(),
+            build_info: BuildInfo::default(),
+        }
+    }
+
+    pub(crate) fn with_revision(mut self, revision: &str) -> Self {
+        self.build_info = self.build_info.with_revision(revision);
+        self
+    }
+
+    pub(crate) fn with_version(mut self, version: &str) -> Self {
+        self.build_info = self.build_info.with_version(version);
+        self
+    }
+
+    pub(crate) fn with_build_info(mut self, build_info: BuildInfo) -> Self {
+        self.build_info = self.build_info.merge(build_info);
+        self
+    }
+
+    pub(crate) fn with_owner(mut self, owner: Owner) -> Self {
+        self.owners.push(owner);
+        self
+    }
+
+    pub(crate) fn with_link(mut self, link: Link) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    /// The name of the application.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The description of the application.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Links to further information about the application.
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// The owners of the application.
+    pub fn owners(&self) -> &[Owner] {
+        &self.owners
+    }
+
+    /// Details of the build of the application.
+    pub fn build_info(&self) -> &BuildInfo {
+        &self.build_info
+    }
+}
+
+/// An owner of an application, and how to reach them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Owner {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    slack: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_call: Option<String>,
+}
+
+impl Owner {
+    /// Creates an [`Owner`](struct.Owner.html) with no contact details.
+    pub fn new(name: &str) -> Self {
+        Owner {
+            name: name.to_owned(),
+            slack: None,
+            email: None,
+            on_call: None,
+        }
+    }
+
+    /// Sets the Slack channel of the owner.
+    pub fn with_slack(mut self, slack: &str) -> Self {
+        self.slack = Some(slack.to_owned());
+        self
+    }
+
+    /// Sets the email address of the owner.
+    pub fn with_email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_owned());
+        self
+    }
+
+    /// Sets the URL of the on-call rota of the owner.
+    pub fn with_on_call(mut self, on_call: &str) -> Self {
+        self.on_call = Some(on_call.to_owned());
+        self
+    }
+
+    /// The name of the owner.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The Slack channel of the owner.
+    pub fn slack(&self) -> Option<&str> {
+        self.slack.as_deref()
+    }
+
+    /// The email address of the owner.
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// The URL of the on-call rota of the owner.
+    pub fn on_call(&self) -> Option<&str> {
+        self.on_call.as_deref()
+    }
+}
+
+/// A link to further information about an application.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Link {
+    description: String,
+    url: String,
+}
+
+impl Link {
+    /// Creates a [`Link`](struct.Link.html).
+    pub fn new(description: &str, url: &str) -> Self {
+        Link {
+            description: description.to_owned(),
+            url: url.to_owned(),
+        }
+    }
+
+    /// What the link is to.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The URL of the link.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::sync::Arc;
+
+use ops_core::Health;
+
+type AggregateFn = dyn Fn(&[CheckHealth<'_>]) -> Health + Send + Sync;
+
+/// Combines the health of every check into the overall health.
+///
+/// The health of a non-critical check is capped at degraded before it is aggregated.
+#[derive(Clone, Default)]
+pub enum Aggregation {
+    /// The overall health is the worst health of any check.
+    #[default]
+    WorstOf,
+    /// Unhealthy when fewer than the given number of checks are available, that is healthy or
+    /// degraded, otherwise the worst health of any check capped at degraded.
+    Quorum(usize),
+    /// Scores each check by its weight, counting degraded checks as half and unhealthy checks in
+    /// full, and compares the weighted share against the thresholds.
+    Weighted {
+        /// The share, between 0 and 1, at which the overall health is degraded.
+        degraded: f64,
+        /// The share, between 0 and 1, at which the overall health is unhealthy.
+        unhealthy: f64,
+    },
+    /// Determines the overall health with a closure.
+    Custom(Arc<AggregateFn>),
+}
+
+impl fmt::Debug for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregation::WorstOf => f.write_str("WorstOf"),
+            Aggregation::Quorum(quorum) => f.debug_tuple("Quorum").field(quorum).finish(),
+            Aggregation::Weighted {
+                degraded,
+                unhealthy,
+            } => f
+                .debug_struct("Weighted")
+                .field("degraded", degraded)
+                .field("unhealthy", unhealthy)
+                .finish(),
+            Aggregation::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl Aggregation {
+    /// Aggregates the health of the checks, an empty list of checks is healthy.
+    pub(crate) fn aggregate(&self, checks: &[CheckHealth<'_>]) -> Health {
+        match self {
+            Aggregation::WorstOf => worst_of(checks),
+            Aggregation::Quorum(quorum) => {
+                let available = checks
+                    .iter()
+                    .filter(|c| c.health != Health::Unhealthy)
+                    .count();
+
+                if available < *quorum {
+                    Health::Unhealthy
+                } else {
+                    worst_of(checks).min(Health::Degraded)
+                }
+            }
+            Aggregation::Weighted {
+                degraded,
+                unhealthy,
+            } => {
+                let total: f64 = checks.iter().map(|c| c.weight).sum();
+
+                if total <= 0.0 {
+                    return Health::Healthy;
+                }
+
+                let score = checks
+                    .iter()
+                    .map(|c| match c.health {
+                        Health::Healthy => 0.0,
+                        Health::Degraded => 0.5 * c.weight,
+                        Health::Unhealthy => c.weight,
+                    })
+                    .sum::<f64>()
+                    / total;
+
+                if score >= *unhealthy {
+                    Health::Unhealthy
+                } else if score >= *degraded {
+                    Health::Degraded
+                } else {
+                    Health::Healthy
+                }
+            }
+            Aggregation::Custom(aggregate) => aggregate(checks),
+        }
+    }
+}
+
+fn worst_of(checks: &[CheckHealth<'_>]) -> Health {
+    checks
+        .iter()
+        .map(|c| c.health)
+        .max()
+        .unwrap_or(Health::Healthy)
+}
+
+/// The health of a single check, as seen by an [`Aggregation`](enum.Aggregation.html).
+#[derive(Clone, Copy, Debug)]
+pub struct CheckHealth<'a> {
+    name: &'a str,
+    health: Health,
+    weight: f64,
+}
+
+impl<'a> CheckHealth<'a> {
+    pub(crate) fn new(name: &'a str, health: Health, critical: bool, weight: f64) -> Self {
+        let health = if critical {
+            health
+        } else {
+            health.min(Health::Degraded)
+        };
+
+        Self {
+            name,
+            health,
+            weight,
+        }
+    }
+
+    /// The name of the check.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// The health of the check, capped at degraded if the check is not critical.
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    /// The weight of the check.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Aggregation, CheckHealth};
+
+    use ops_core::Health;
+
+    fn check(health: Health) -> CheckHealth<'static> {
+        CheckHealth::new("check", health, true, 1.0)
+    }
+
+    fn weighted(health: Health, weight: f64) -> CheckHealth<'static> {
+        CheckHealth::new("check", health, true, weight)
+    }
+
+    #[test]
+    fn no_checks_are_healthy() {
+        let aggregations = vec![
+            Aggregation::WorstOf,
+            Aggregation::Quorum(0),
+            Aggregation::Weighted {
+                degraded: 0.1,
+                unhealthy: 0.5,
+            },
+        ];
+
+        for aggregation in aggregations {
+            assert_eq!(
+                aggregation.aggregate(&[]),
+                Health::Healthy,
+                "{:?}",
+                aggregation
+            );
+        }
+    }
+
+    #[test]
+    fn worst_of() {
+        let checks = [
+            check(Health::Healthy),
+            check(Health::Unhealthy),
+            check(Health::Degraded),
+        ];
+
+        assert_eq!(Aggregation::WorstOf.aggregate(&checks), Health::Unhealthy);
+    }
+
+    #[test]
+    fn non_critical_checks_are_capped_at_degraded() {
+        let check = CheckHealth::new("check", Health::Unhealthy, false, 1.0);
+
+        assert_eq!(check.health(), Health::Degraded);
+        assert_eq!(Aggregation::WorstOf.aggregate(&[check]), Health::Degraded);
+
+        let check = CheckHealth::new("check", Health::Healthy, false, 1.0);
+
+        assert_eq!(check.health(), Health::Healthy);
+    }
+
+    #[test]
+    fn met_quorum_is_capped_at_degraded() {
+        let checks = [
+            check(Health::Healthy),
+            check(Health::Healthy),
+            check(Health::Unhealthy),
+        ];
+
+        assert_eq!(Aggregation::Quorum(2).aggregate(&checks), Health::Degraded);
+        assert_eq!(Aggregation::Quorum(3).aggregate(&checks), Health::Unhealthy);
+    }
+
+    #[test]
+    fn quorum_counts_degraded_checks_as_available() {
+        let checks = [check(Health::Degraded), check(Health::Unhealthy)];
+
+        assert_eq!(Aggregation::Quorum(1).aggregate(&checks), Health::Degraded);
+        assert_eq!(
+            Aggregation::Quorum(1).aggregate(&[check(Health::Healthy)]),
+            Health::Healthy
+        );
+    }
+
+    #[test]
+    fn weighted_shares() {
+        let aggregation = Aggregation::Weighted {
+            degraded: 0.25,
+            unhealthy: 0.5,
+        };
+
+        let checks = [
+            weighted(Health::Unhealthy, 1.0),
+            weighted(Health::Healthy, 3.0),
+        ];
+        assert_eq!(aggregation.aggregate(&checks), Health::Degraded);
+
+        let checks = [
+            weighted(Health::Unhealthy, 1.0),
+            weighted(Health::Degraded, 2.0),
+            weighted(Health::Healthy, 1.0),
+        ];
+        assert_eq!(aggregation.aggregate(&checks), Health::Unhealthy);
+
+        let checks = [
+            weighted(Health::Degraded, 1.0),
+            weighted(Health::Healthy, 3.0),
+        ];
+        assert_eq!(aggregation.aggregate(&checks), Health::Healthy);
+    }
+
+    #[test]
+    fn weighted_without_weight_is_healthy() {
+        let aggregation = Aggregation::Weighted {
+            degraded: 0.0,
+            unhealthy: 0.0,
+        };
+
+        let checks = [weighted(Health::Unhealthy, 0.0)];
+
+        assert_eq!(aggregation.aggregate(&checks), Health::Healthy);
+    }
+
+    #[test]
+    fn custom() {
+        let aggregation = Aggregation::Custom(Arc::new(|checks| {
+            if checks
+                .iter()
+                .any(|c| c.name() == "db" && c.health() == Health::Unhealthy)
+            {
+                Health::Unhealthy
+            } else {
+                Health::Healthy
+            }
+        }));
+
+        let checks = [
+            CheckHealth::new("cache", Health::Unhealthy, true, 1.0),
+            CheckHealth::new("db", Health::Healthy, true, 1.0),
+        ];
+        assert_eq!(aggregation.aggregate(&checks), Health::Healthy);
+
+        let checks = [CheckHealth::new("db", Health::Unhealthy, true, 1.0)];
+        assert_eq!(aggregation.aggregate(&checks), Health::Unhealthy);
+    }
+}
@@ -0,0 +1,209 @@
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Details of the build of an application.
+///
+/// Use [`build_info!`](macro.build_info.html) to fill them in at compile time.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildInfo {
+    revision: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    build_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rustc_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+}
+
+impl BuildInfo {
+    /// Creates an empty [`BuildInfo`](struct.BuildInfo.html).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the revision, this should be a version control ref.
+    pub fn with_revision(mut self, revision: &str) -> Self {
+        self.revision = Some(revision.to_owned());
+        self
+    }
+
+    /// Sets the version of the application.
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_owned());
+        self
+    }
+
+    /// Sets when the application was built, this should be an RFC 3339 timestamp.
+    pub fn with_build_time(mut self, build_time: &str) -> Self {
+        self.build_time = Some(build_time.to_owned());
+        self
+    }
+
+    /// Sets the version of the compiler the application was built with.
+    pub fn with_rustc_version(mut self, rustc_version: &str) -> Self {
+        self.rustc_version = Some(rustc_version.to_owned());
+        self
+    }
+
+    /// Sets the target triple the application was built for.
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_owned());
+        self
+    }
+
+    /// The revision the application was built from, usually a version control ref.
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    /// The version of the application.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// When the application was built.
+    pub fn build_time(&self) -> Option<&str> {
+        self.build_time.as_deref()
+    }
+
+    /// The version of the compiler the application was built with.
+    pub fn rustc_version(&self) -> Option<&str> {
+        self.rustc_version.as_deref()
+    }
+
+    /// The target triple the application was built for.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Takes the fields that are set in `other`, keeping the rest.
+    pub(crate) fn merge(self, other: BuildInfo) -> Self {
+        BuildInfo {
+            revision: other.revision.or(self.revision),
+            version: other.version.or(self.version),
+            build_time: other.build_time.or(self.build_time),
+            rustc_version: other.rustc_version.or(self.rustc_version),
+            target: other.target.or(self.target),
+        }
+    }
+}
+
+/// Builds a [`BuildInfo`](struct.BuildInfo.html) at compile time.
+///
+/// The version is taken from `CARGO_PKG_VERSION` of the calling crate. The build time, compiler
+/// version and target are taken from the variables set by calling
+/// [`emit_build_env`](fn.emit_build_env.html) in its build script, and are left out without it.
+#[macro_export]
+macro_rules! build_info {
+    () => {{
+        let mut build_info = $crate::BuildInfo::new().with_version(env!("CARGO_PKG_VERSION"));
+        if let Some(build_time) = option_env!("OPS_BUILD_TIME") {
+            build_info = build_info.with_build_time(build_time);
+        }
+        if let Some(rustc_version) = option_env!("OPS_RUSTC_VERSION") {
+            build_info = build_info.with_rustc_version(rustc_version);
+        }
+        if let Some(target) = option_env!("OPS_TARGET") {
+            build_info = build_info.with_target(target);
+        }
+        build_info
+    }};
+}
+
+/// Sets the variables read by [`build_info!`](macro.build_info.html), to be called from a build
+/// script.
+///
+/// The build time honours `SOURCE_DATE_EPOCH`, for reproducible builds.
+pub fn emit_build_env() {
+    let build_time = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+    println!("cargo:rustc-env=OPS_BUILD_TIME={}", rfc3339(build_time));
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    if let Some(rustc_version) = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+    {
+        println!("cargo:rustc-env=OPS_RUSTC_VERSION={}", rustc_version.trim());
+    }
+
+    if let Ok(target) = env::var("TARGET") {
+        println!("cargo:rustc-env=OPS_TARGET={}", target);
+    }
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 timestamp in UTC.
+fn rfc3339(secs: u64) -> String {
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+
+    // Converts days since the epoch to a civil date, from Howard Hinnant's chrono-compatible
+    // low-level date algorithms
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(946_684_799), "1999-12-31T23:59:59Z");
+        assert_eq!(rfc3339(1_704_067_199), "2023-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn merge_keeps_the_fields_not_set() {
+        let build_info = BuildInfo::new()
+            .with_revision("abc123")
+            .with_version("1.0.0")
+            .with_target("x86_64-unknown-linux-gnu")
+            .merge(
+                BuildInfo::new()
+                    .with_version("1.1.0")
+                    .with_build_time("2020-01-01T00:00:00Z"),
+            );
+
+        assert_eq!(build_info.revision(), Some("abc123"));
+        assert_eq!(build_info.version(), Some("1.1.0"));
+        assert_eq!(build_info.build_time(), Some("2020-01-01T00:00:00Z"));
+        assert_eq!(build_info.rustc_version(), None);
+        assert_eq!(build_info.target(), Some("x86_64-unknown-linux-gnu"));
+    }
+}
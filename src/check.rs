@@ -1,17 +1,38 @@
 use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
-use ops_core::{async_trait, CheckResponse, Checker};
+use crate::aggregation::{Aggregation, CheckHealth};
+use crate::status;
+
+use once_cell::sync::OnceCell;
+use ops_core::{async_trait, CheckResponse, Checker, Health};
 
 /// Associates a name with a [`Checker`](trait.Checker.html).
 pub struct NamedChecker {
     name: String,
-    checker: Box<dyn Checker>,
+    // Built on first use for a group, once its builders have all been applied
+    checker: OnceCell<Box<dyn Checker>>,
+    group: Option<Group>,
+    timeout: Option<Duration>,
+    interval: Option<Duration>,
+    scope: Scope,
+    critical: bool,
+    weight: f64,
+}
+
+/// Which endpoints a checker contributes to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Scope {
+    Both,
+    Health,
+    Readiness,
 }
 
 #[async_trait]
 impl Checker for NamedChecker {
     async fn check(&self) -> CheckResponse {
-        self.checker.check().await
+        self.checker().check().await
     }
 }
 
@@ -19,6 +40,12 @@ impl fmt::Debug for NamedChecker {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("NamedChecker")
             .field("name", &self.name)
+            .field("group", &self.group)
+            .field("timeout", &self.timeout)
+            .field("interval", &self.interval)
+            .field("scope", &self.scope)
+            .field("critical", &self.critical)
+            .field("weight", &self.weight)
             .finish()
     }
 }
@@ -28,8 +55,93 @@ impl NamedChecker {
     pub fn new(name: &str, checker: Box<dyn Checker>) -> Self {
         Self {
             name: safe_metric_name(name),
-            checker,
+            checker: OnceCell::from(checker),
+            group: None,
+            timeout: None,
+            interval: None,
+            scope: Scope::Both,
+            critical: true,
+            weight: 1.0,
+        }
+    }
+
+    /// Creates a [`NamedChecker`](struct.NamedChecker.html) for a group of checkers, whose
+    /// health is combined into the health of the group.
+    ///
+    /// The checkers of a group are run with their own timeouts, and the group is reported with
+    /// their results nested inside. Whether they affect health or readiness is decided by the
+    /// group. A [timeout](#method.with_timeout) set on the group limits the whole group, which
+    /// is reported as unhealthy without any nested results when it is exceeded.
+    pub fn group(name: &str, checkers: Vec<NamedChecker>) -> Self {
+        let name = safe_metric_name(name);
+
+        let group = Group {
+            name: name.clone(),
+            checkers: Arc::new(checkers),
+            aggregation: Aggregation::default(),
+        };
+
+        Self {
+            name,
+            checker: OnceCell::new(),
+            group: Some(group),
+            timeout: None,
+            interval: None,
+            scope: Scope::Both,
+            critical: true,
+            weight: 1.0,
+        }
+    }
+
+    /// Sets how the health of the checkers of a group is combined, defaults to
+    /// [`Aggregation::WorstOf`](enum.Aggregation.html#variant.WorstOf).
+    ///
+    /// This has no effect on a checker that is not a group.
+    pub fn with_aggregation(mut self, aggregation: Aggregation) -> Self {
+        if let Some(group) = &mut self.group {
+            group.aggregation = aggregation;
         }
+        self
+    }
+
+    /// Sets a timeout for this checker, overriding the default of the status.
+    ///
+    /// For a group, this limits the whole group rather than being a default for its checkers.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the interval this checker is run at in the background, overriding the default of the
+    /// status.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Only uses this checker to determine readiness, it is left out of the health checks.
+    pub fn readiness_only(mut self) -> Self {
+        self.scope = Scope::Readiness;
+        self
+    }
+
+    /// Only uses this checker in the health checks, it does not affect readiness.
+    pub fn health_only(mut self) -> Self {
+        self.scope = Scope::Health;
+        self
+    }
+
+    /// Marks this checker as non-critical, its failures degrade the overall health at worst.
+    pub fn non_critical(mut self) -> Self {
+        self.critical = false;
+        self
+    }
+
+    /// Sets the weight of this checker for a weighted [`Aggregation`](enum.Aggregation.html),
+    /// defaults to 1.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
     }
 
     /// The name of the checker.
@@ -38,9 +150,137 @@ impl NamedChecker {
     }
 
     /// The actual checker itself
+    ///
+    /// For a group, this runs the checkers of the group as a status would, with the default
+    /// timeout of a status for those that do not set their own, and combines their health.
     #[allow(clippy::borrowed_box)]
     pub fn checker(&self) -> &Box<dyn Checker> {
-        &self.checker
+        self.checker.get_or_init(|| {
+            let group = self
+                .group
+                .clone()
+                .expect("only groups are built on first use");
+
+            Box::new(GroupChecker {
+                group,
+                timeout: self.timeout,
+            })
+        })
+    }
+
+    /// The checkers of the group, if this is a group.
+    pub fn checkers(&self) -> Option<&[NamedChecker]> {
+        self.group.as_ref().map(|group| group.checkers.as_slice())
+    }
+
+    pub(crate) fn as_group(&self) -> Option<&Group> {
+        self.group.as_ref()
+    }
+
+    /// The timeout of the checker, if it overrides the default of the status.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// The background interval of the checker, if it overrides the default of the status.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Whether a failure of the checker can make the overall health unhealthy.
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    /// The weight of the checker.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Whether the checker is part of the health checks.
+    pub fn affects_health(&self) -> bool {
+        self.scope != Scope::Readiness
+    }
+
+    /// Whether the checker determines readiness.
+    pub fn affects_readiness(&self) -> bool {
+        self.scope != Scope::Health
+    }
+}
+
+/// The checkers of a group, combined into a single check.
+#[derive(Clone)]
+pub(crate) struct Group {
+    name: String,
+    checkers: Arc<Vec<NamedChecker>>,
+    aggregation: Aggregation,
+}
+
+impl fmt::Debug for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Group")
+            .field("checkers", &self.checkers)
+            .field("aggregation", &self.aggregation)
+            .finish()
+    }
+}
+
+/// The checker of a group, for running it outside of a status.
+struct GroupChecker {
+    group: Group,
+    timeout: Option<Duration>,
+}
+
+#[async_trait]
+impl Checker for GroupChecker {
+    async fn check(&self) -> CheckResponse {
+        status::check_group(&self.group, self.timeout).await
+    }
+}
+
+impl Group {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn checkers(&self) -> &[NamedChecker] {
+        &self.checkers
+    }
+
+    /// Combines the health and output of each checker into the response of the group.
+    pub(crate) fn combine(&self, results: &[(&NamedChecker, Health, &str)]) -> CheckResponse {
+        let health = self.aggregation.aggregate(
+            &results
+                .iter()
+                .map(|(c, health, _)| {
+                    CheckHealth::new(c.name(), *health, c.is_critical(), c.weight())
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let failed = results
+            .iter()
+            .filter(|(_, health, _)| *health != Health::Healthy)
+            .map(|(c, health, output)| {
+                let health: &'static str = (*health).into();
+                format!("{} is {}: {}", c.name(), health, output)
+            })
+            .collect::<Vec<_>>();
+
+        let output = if failed.is_empty() {
+            "all checks are healthy".to_owned()
+        } else {
+            failed.join("; ")
+        };
+
+        let action = format!("Investigate the failing checks of {}", self.name);
+        let impact = format!("{} is not working as expected", self.name);
+
+        match health {
+            Health::Healthy => CheckResponse::healthy(&output),
+            Health::Degraded => CheckResponse::degraded(&output, &action),
+            Health::Unhealthy => CheckResponse::unhealthy(&output, &action, &impact),
+        }
     }
 }
 
@@ -53,3 +293,87 @@ fn safe_metric_name(metric_name: &str) -> String {
         })
         .collect::<String>()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::NamedChecker;
+    use crate::status::Status;
+    use crate::StatusBuilder;
+
+    use ops_core::{async_trait, CheckResponse, Checker, Health};
+
+    struct Slow(Duration);
+
+    #[async_trait]
+    impl Checker for Slow {
+        async fn check(&self) -> CheckResponse {
+            tokio::time::sleep(self.0).await;
+            CheckResponse::healthy("ok")
+        }
+    }
+
+    struct Panics;
+
+    #[async_trait]
+    impl Checker for Panics {
+        async fn check(&self) -> CheckResponse {
+            panic!("oops")
+        }
+    }
+
+    fn slow(name: &str, millis: u64) -> NamedChecker {
+        NamedChecker::new(name, Box::new(Slow(Duration::from_millis(millis))))
+    }
+
+    #[tokio::test]
+    async fn group_checker_isolates_its_checkers() {
+        let group = NamedChecker::group(
+            "group",
+            vec![
+                slow("fast", 0),
+                slow("slow", 5000).with_timeout(Duration::from_millis(50)),
+                NamedChecker::new("panics", Box::new(Panics)),
+            ],
+        );
+
+        let resp = group.check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp
+            .output()
+            .starts_with("slow is unhealthy: check timed out after"));
+        assert!(resp
+            .output()
+            .ends_with("panics is unhealthy: check panicked: oops"));
+    }
+
+    #[tokio::test]
+    async fn group_checker_applies_the_timeout_of_the_group() {
+        let group = NamedChecker::group("group", vec![slow("slow", 5000)])
+            .with_timeout(Duration::from_millis(50));
+
+        let resp = group.checker().check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp.output().starts_with("check timed out after"));
+    }
+
+    #[tokio::test]
+    async fn status_applies_the_timeout_of_a_group() {
+        let status = StatusBuilder::healthchecks("app", "")
+            .check_timeout(Duration::from_secs(5))
+            .checker(
+                NamedChecker::group("group", vec![slow("fast", 0), slow("slow", 1000)])
+                    .with_timeout(Duration::from_millis(50)),
+            );
+
+        let report = status.check().await.unwrap();
+        let group = &report.checks()[0];
+
+        assert_eq!(group.health(), Health::Unhealthy);
+        assert!(group.output().starts_with("check timed out after"));
+        assert!(group.checks().is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use std::io::Write;
+
+use crate::Result;
+
+/// The smallest body that is compressed by default, smaller bodies are not worth the overhead.
+pub(crate) const DEFAULT_COMPRESSION_MIN_SIZE: usize = 1024;
+
+/// A content coding the body of a response can be compressed with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Encoding {
+    /// No compression.
+    Identity,
+    /// Gzip compression.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard compression.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Encoding {
+    /// Picks the supported coding with the highest quality in the `Accept-Encoding` header,
+    /// defaulting to no compression.
+    pub(crate) fn negotiate(accept_encoding: Option<&str>) -> Encoding {
+        let mut best = (Encoding::Identity, 0.0);
+
+        for coding in accept_encoding.unwrap_or_default().split(',') {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+
+            let quality = params
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f64>().ok())
+                .unwrap_or(1.0);
+
+            let encoding = match Encoding::from_name(name) {
+                Some(encoding) => encoding,
+                None => continue,
+            };
+
+            // Earlier codings win ties, as clients tend to list their preferred coding first
+            if quality > best.1 {
+                best = (encoding, quality);
+            }
+        }
+
+        best.0
+    }
+
+    /// The coding with the given name, if it is one we support.
+    fn from_name(name: &str) -> Option<Encoding> {
+        #[cfg(feature = "gzip")]
+        if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+            return Some(Encoding::Gzip);
+        }
+
+        #[cfg(feature = "zstd")]
+        if name.eq_ignore_ascii_case("zstd") {
+            return Some(Encoding::Zstd);
+        }
+
+        if name.eq_ignore_ascii_case("identity") {
+            return Some(Encoding::Identity);
+        }
+
+        None
+    }
+
+    /// The value of the `Content-Encoding` header for the coding.
+    pub(crate) fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Encoding::Identity => None,
+            #[cfg(feature = "gzip")]
+            Encoding::Gzip => Some("gzip"),
+            #[cfg(feature = "zstd")]
+            Encoding::Zstd => Some("zstd"),
+        }
+    }
+}
+
+/// A response body, compressed if the client accepts it.
+#[derive(Debug)]
+pub(crate) struct Compressed {
+    pub(crate) encoding: Encoding,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Compresses the body with the coding negotiated from the `Accept-Encoding` header, if it is at
+/// least `min_size` bytes.
+pub(crate) fn compress(
+    body: Vec<u8>,
+    accept_encoding: Option<&str>,
+    min_size: usize,
+) -> Result<Compressed> {
+    if body.len() < min_size {
+        return Ok(Compressed {
+            encoding: Encoding::Identity,
+            body,
+        });
+    }
+
+    let encoding = Encoding::negotiate(accept_encoding);
+
+    let body = match encoding {
+        Encoding::Identity => body,
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(
+                Vec::with_capacity(body.len() / 4),
+                flate2::Compression::default(),
+            );
+            encoder.write_all(&body)?;
+            encoder.finish()?
+        }
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => {
+            let mut encoder = zstd::Encoder::new(
+                Vec::with_capacity(body.len() / 4),
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?;
+            encoder.write_all(&body)?;
+            encoder.finish()?
+        }
+    };
+
+    Ok(Compressed { encoding, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, Encoding};
+
+    #[test]
+    fn negotiate_defaults_to_identity() {
+        assert_eq!(Encoding::negotiate(None), Encoding::Identity);
+        assert_eq!(Encoding::negotiate(Some("")), Encoding::Identity);
+        assert_eq!(Encoding::negotiate(Some("br, deflate")), Encoding::Identity);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn negotiate_gzip() {
+        assert_eq!(Encoding::negotiate(Some("br, gzip")), Encoding::Gzip);
+        assert_eq!(Encoding::negotiate(Some("X-GZIP")), Encoding::Gzip);
+        assert_eq!(Encoding::negotiate(Some("gzip;q=0")), Encoding::Identity);
+        assert_eq!(
+            Encoding::negotiate(Some("gzip;q=0.5, identity")),
+            Encoding::Identity
+        );
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn negotiate_picks_the_highest_quality() {
+        assert_eq!(
+            Encoding::negotiate(Some("gzip;q=0.5, zstd;q=0.8")),
+            Encoding::Zstd
+        );
+        assert_eq!(
+            Encoding::negotiate(Some("zstd;q=0.5, gzip")),
+            Encoding::Gzip
+        );
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn negotiate_prefers_earlier_codings_on_ties() {
+        assert_eq!(Encoding::negotiate(Some("zstd, gzip")), Encoding::Zstd);
+        assert_eq!(Encoding::negotiate(Some("gzip, zstd")), Encoding::Gzip);
+    }
+
+    #[test]
+    fn small_bodies_are_not_compressed() {
+        let compressed = compress(b"small".to_vec(), Some("gzip, zstd"), 1024).unwrap();
+
+        assert_eq!(compressed.encoding, Encoding::Identity);
+        assert_eq!(compressed.body, b"small");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compress_gzip() {
+        use std::io::Read;
+
+        let body = "metric 1\n".repeat(200).into_bytes();
+        let compressed = compress(body.clone(), Some("gzip"), 1024).unwrap();
+
+        assert_eq!(compressed.encoding, Encoding::Gzip);
+        assert!(compressed.body.len() < body.len());
+
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(compressed.body.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compress_zstd() {
+        let body = "metric 1\n".repeat(200).into_bytes();
+        let compressed = compress(body.clone(), Some("zstd"), 1024).unwrap();
+
+        assert_eq!(compressed.encoding, Encoding::Zstd);
+        assert!(compressed.body.len() < body.len());
+        assert_eq!(zstd::decode_all(compressed.body.as_slice()).unwrap(), body);
+    }
+}
@@ -0,0 +1,280 @@
+use std::ffi::CString;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use futures_channel::oneshot;
+use ops_core::{async_trait, CheckResponse, Checker, Health};
+
+type Read = Result<FsStats, String>;
+
+/// A lower bound on free space or free inodes.
+#[derive(Clone, Copy, Debug)]
+pub enum Threshold {
+    /// A percentage of the total, between 0 and 100.
+    Percent(f64),
+    /// An absolute amount, in bytes for free space or a count for free inodes.
+    Absolute(u64),
+}
+
+impl Threshold {
+    fn is_breached(self, free: u64, total: u64) -> bool {
+        match self {
+            Threshold::Percent(percent) => percent_of(free, total) < percent,
+            Threshold::Absolute(min) => free < min,
+        }
+    }
+}
+
+/// Checks the free space and free inodes of the filesystem containing a path.
+///
+/// The stats are read on a thread, shared by checks that overlap, as reading them blocks for as
+/// long as a network volume is unresponsive.
+#[derive(Debug)]
+pub struct DiskChecker {
+    path: PathBuf,
+    // The checks waiting on the running read, `None` when none is running
+    waiting: Arc<Mutex<Option<Vec<oneshot::Sender<Read>>>>>,
+    degraded_space: Threshold,
+    unhealthy_space: Threshold,
+    degraded_inodes: Threshold,
+    unhealthy_inodes: Threshold,
+}
+
+impl DiskChecker {
+    /// Creates a [`DiskChecker`](struct.DiskChecker.html) for the filesystem containing the path,
+    /// which is degraded below 10% and unhealthy below 5% of free space or free inodes.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        DiskChecker {
+            path: path.as_ref().to_owned(),
+            waiting: Arc::new(Mutex::new(None)),
+            degraded_space: Threshold::Percent(10.0),
+            unhealthy_space: Threshold::Percent(5.0),
+            degraded_inodes: Threshold::Percent(10.0),
+            unhealthy_inodes: Threshold::Percent(5.0),
+        }
+    }
+
+    /// Reports the check as degraded when free space drops below the threshold.
+    pub fn degraded_free_space(mut self, threshold: Threshold) -> Self {
+        self.degraded_space = threshold;
+        self
+    }
+
+    /// Reports the check as unhealthy when free space drops below the threshold.
+    pub fn unhealthy_free_space(mut self, threshold: Threshold) -> Self {
+        self.unhealthy_space = threshold;
+        self
+    }
+
+    /// Reports the check as degraded when free inodes drop below the threshold.
+    pub fn degraded_free_inodes(mut self, threshold: Threshold) -> Self {
+        self.degraded_inodes = threshold;
+        self
+    }
+
+    /// Reports the check as unhealthy when free inodes drop below the threshold.
+    pub fn unhealthy_free_inodes(mut self, threshold: Threshold) -> Self {
+        self.unhealthy_inodes = threshold;
+        self
+    }
+
+    fn health(&self, free: u64, total: u64, degraded: Threshold, unhealthy: Threshold) -> Health {
+        if unhealthy.is_breached(free, total) {
+            Health::Unhealthy
+        } else if degraded.is_breached(free, total) {
+            Health::Degraded
+        } else {
+            Health::Healthy
+        }
+    }
+}
+
+#[async_trait]
+impl Checker for DiskChecker {
+    async fn check(&self) -> CheckResponse {
+        let volume = self.path.display();
+
+        let unreadable = |err: &dyn std::fmt::Display| {
+            CheckResponse::unhealthy(
+                &format!("failed to read the filesystem stats of {}: {}", volume, err),
+                &format!("Check that volume {} is mounted", volume),
+                &format!("Volume {} cannot be monitored", volume),
+            )
+        };
+
+        let (tx, rx) = oneshot::channel();
+
+        let start = {
+            let mut waiting = self.waiting.lock().unwrap_or_else(PoisonError::into_inner);
+
+            match &mut *waiting {
+                Some(waiters) => {
+                    waiters.retain(|waiter| !waiter.is_canceled());
+                    waiters.push(tx);
+                    false
+                }
+                None => {
+                    *waiting = Some(vec![tx]);
+                    true
+                }
+            }
+        };
+
+        if start {
+            let path = self.path.clone();
+            let waiting = self.waiting.clone();
+
+            // statvfs blocks, so it is kept off the async runtime
+            thread::spawn(move || {
+                let stats = FsStats::read(&path).map_err(|err| err.to_string());
+
+                let waiters = waiting
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .take()
+                    .unwrap_or_default();
+
+                for waiter in waiters {
+                    let _ = waiter.send(stats.clone());
+                }
+            });
+        }
+
+        let stats = match rx.await {
+            Ok(Ok(stats)) => stats,
+            Ok(Err(err)) => return unreadable(&err),
+            Err(_) => return unreadable(&"read was aborted"),
+        };
+
+        let space_health = self.health(
+            stats.free_bytes,
+            stats.total_bytes,
+            self.degraded_space,
+            self.unhealthy_space,
+        );
+
+        // Some filesystems, such as btrfs, have no fixed number of inodes
+        let inode_health = if stats.total_inodes == 0 {
+            Health::Healthy
+        } else {
+            self.health(
+                stats.free_inodes,
+                stats.total_inodes,
+                self.degraded_inodes,
+                self.unhealthy_inodes,
+            )
+        };
+
+        let output = format!(
+            "{} has {} ({:.1}%) free space and {} ({:.1}%) free inodes",
+            volume,
+            format_bytes(stats.free_bytes),
+            percent_of(stats.free_bytes, stats.total_bytes),
+            stats.free_inodes,
+            percent_of(stats.free_inodes, stats.total_inodes),
+        );
+
+        let action = if space_health >= inode_health {
+            format!("Free space on volume {}", volume)
+        } else {
+            format!("Free inodes on volume {}", volume)
+        };
+
+        match space_health.max(inode_health) {
+            Health::Healthy => CheckResponse::healthy(&output),
+            Health::Degraded => CheckResponse::degraded(&output, &action),
+            Health::Unhealthy => CheckResponse::unhealthy(
+                &output,
+                &action,
+                &format!("Writes to volume {} will fail", volume),
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FsStats {
+    free_bytes: u64,
+    total_bytes: u64,
+    free_inodes: u64,
+    total_inodes: u64,
+}
+
+impl FsStats {
+    // The statvfs field types differ between platforms
+    #[allow(clippy::useless_conversion)]
+    fn read(path: &Path) -> io::Result<Self> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+
+        // SAFETY: `path` is a valid C string and `stats` is only read once statvfs has filled it
+        let stats = unsafe {
+            if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            stats.assume_init()
+        };
+
+        let fragment_size = u64::from(stats.f_frsize);
+
+        Ok(FsStats {
+            free_bytes: u64::from(stats.f_bavail) * fragment_size,
+            total_bytes: u64::from(stats.f_blocks) * fragment_size,
+            free_inodes: u64::from(stats.f_favail),
+            total_inodes: u64::from(stats.f_files),
+        })
+    }
+}
+
+fn percent_of(free: u64, total: u64) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        free as f64 / total as f64 * 100.0
+    }
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiskChecker;
+
+    use ops_core::{Checker, Health};
+
+    #[tokio::test]
+    async fn overlapping_checks_share_the_read() {
+        let checker = DiskChecker::new("/");
+
+        let (first, second) = tokio::join!(checker.check(), checker.check());
+
+        assert!(first.output().starts_with("/ has "));
+        assert_eq!(first.output(), second.output());
+    }
+
+    #[tokio::test]
+    async fn missing_volume_is_unhealthy() {
+        let resp = DiskChecker::new("/does/not/exist").check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp
+            .output()
+            .starts_with("failed to read the filesystem stats of /does/not/exist: "));
+    }
+}
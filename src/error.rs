@@ -3,7 +3,7 @@ use std::{error, fmt};
 /// Errors that can happen when running an ops server.
 #[derive(Debug)]
 pub enum Error {
-    #[cfg(feature = "hyper_server")]
+    #[cfg(feature = "hyper")]
     /// Hyper error
     Hyper(hyper::Error),
     /// JSON error
@@ -11,25 +11,36 @@ pub enum Error {
     /// UTF-8 Decoding error
     Utf8(std::string::FromUtf8Error),
     /// HTTP error
-    #[cfg(feature = "hyper_server")]
+    #[cfg(feature = "hyper")]
     Http(hyper::http::Error),
     /// Prometheus error
     Prometheus(prometheus::Error),
     /// Address parsing error
     ParseAddress(std::net::AddrParseError),
+    /// I/O error
+    Io(std::io::Error),
+    /// Status code that cannot be used for a health response
+    InvalidStatusCode(u16),
+    /// URL with a scheme other than `http`
+    UnsupportedScheme(String),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
-            #[cfg(feature = "hyper_server")]
+            #[cfg(feature = "hyper")]
             Error::Hyper(ref err) => err.fmt(f),
             Error::Json(ref err) => err.fmt(f),
             Error::Utf8(ref err) => err.fmt(f),
-            #[cfg(feature = "hyper_server")]
+            #[cfg(feature = "hyper")]
             Error::Http(ref err) => err.fmt(f),
             Error::Prometheus(ref err) => err.fmt(f),
             Error::ParseAddress(ref err) => err.fmt(f),
+            Error::Io(ref err) => err.fmt(f),
+            Error::InvalidStatusCode(code) => write!(f, "invalid health status code {}", code),
+            Error::UnsupportedScheme(ref url) => {
+                write!(f, "unsupported scheme in {}, only http is supported", url)
+            }
         }
     }
 }
@@ -37,19 +48,22 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
-            #[cfg(feature = "hyper_server")]
+            #[cfg(feature = "hyper")]
             Error::Hyper(ref err) => Some(err),
             Error::Json(ref err) => Some(err),
             Error::Utf8(ref err) => Some(err),
-            #[cfg(feature = "hyper_server")]
+            #[cfg(feature = "hyper")]
             Error::Http(ref err) => Some(err),
             Error::Prometheus(ref err) => Some(err),
             Error::ParseAddress(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
+            Error::InvalidStatusCode(_) => None,
+            Error::UnsupportedScheme(_) => None,
         }
     }
 }
 
-#[cfg(feature = "hyper_server")]
+#[cfg(feature = "hyper")]
 impl From<hyper::Error> for Error {
     fn from(err: hyper::Error) -> Self {
         Self::Hyper(err)
@@ -68,7 +82,7 @@ impl From<std::string::FromUtf8Error> for Error {
     }
 }
 
-#[cfg(feature = "hyper_server")]
+#[cfg(feature = "hyper")]
 impl From<hyper::http::Error> for Error {
     fn from(err: hyper::http::Error) -> Self {
         Self::Http(err)
@@ -86,3 +100,9 @@ impl From<std::net::AddrParseError> for Error {
         Self::ParseAddress(err)
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt::Write;
+
+use crate::Result;
+
+use prometheus::proto::{LabelPair, Metric, MetricFamily, MetricType};
+#[cfg(feature = "protobuf")]
+use prometheus::ProtobufEncoder;
+use prometheus::{Encoder, TextEncoder};
+
+const TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPENMETRICS_TYPE: &str = "application/openmetrics-text";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+#[cfg(feature = "protobuf")]
+const PROTOBUF_TYPE: &str = "application/vnd.google.protobuf";
+#[cfg(feature = "protobuf")]
+const PROTOBUF_CONTENT_TYPE: &str =
+    "application/vnd.google.protobuf; proto=io.prometheus.client.MetricFamily; encoding=delimited";
+
+/// A metrics exposition format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Format {
+    /// The Prometheus text format, version 0.0.4.
+    Text,
+    /// The OpenMetrics text format, version 1.0.0.
+    OpenMetrics,
+    /// The delimited protobuf format of `io.prometheus.client.MetricFamily` messages.
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+}
+
+impl Format {
+    /// Picks the format with the highest quality in the `Accept` header, defaulting to text.
+    pub(crate) fn negotiate(accept: Option<&str>) -> Format {
+        let mut best = (Format::Text, 0.0);
+
+        for range in accept.unwrap_or_default().split(',') {
+            let mut params = range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default();
+            let params = params.collect::<Vec<_>>();
+
+            let quality = params
+                .iter()
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f64>().ok())
+                .unwrap_or(1.0);
+
+            let format = match Format::from_media_range(media_type, &params) {
+                Some(format) => format,
+                None => continue,
+            };
+
+            // Earlier ranges win ties, as scrapers list their preferred format first
+            if quality > best.1 {
+                best = (format, quality);
+            }
+        }
+
+        best.0
+    }
+
+    /// The format of a single media range, if it is one we can serve.
+    #[cfg_attr(not(feature = "protobuf"), allow(unused_variables))]
+    fn from_media_range(media_type: &str, params: &[&str]) -> Option<Format> {
+        if media_type.eq_ignore_ascii_case(OPENMETRICS_TYPE) {
+            return Some(Format::OpenMetrics);
+        }
+
+        if media_type.eq_ignore_ascii_case("text/plain") || media_type == "*/*" {
+            return Some(Format::Text);
+        }
+
+        // Only the delimited encoding of metric families is supported, the other protobuf
+        // encodings are text based and never made it into Prometheus
+        #[cfg(feature = "protobuf")]
+        if media_type.eq_ignore_ascii_case(PROTOBUF_TYPE)
+            && params.contains(&"encoding=delimited")
+            && params
+                .iter()
+                .filter_map(|param| param.strip_prefix("proto="))
+                .all(|proto| proto == "io.prometheus.client.MetricFamily")
+        {
+            return Some(Format::Protobuf);
+        }
+
+        None
+    }
+}
+
+/// Metrics rendered in an exposition format.
+#[derive(Debug)]
+pub(crate) struct Rendered {
+    pub(crate) content_type: &'static str,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Renders the metric families in the format negotiated from the `Accept` header.
+pub(crate) fn render(families: &[MetricFamily], accept: Option<&str>) -> Result<Rendered> {
+    match Format::negotiate(accept) {
+        Format::Text => {
+            let mut body = Vec::<u8>::new();
+            TextEncoder::new().encode(families, &mut body)?;
+
+            Ok(Rendered {
+                content_type: TEXT_CONTENT_TYPE,
+                body,
+            })
+        }
+        Format::OpenMetrics => Ok(Rendered {
+            content_type: OPENMETRICS_CONTENT_TYPE,
+            body: encode_openmetrics(families).into_bytes(),
+        }),
+        #[cfg(feature = "protobuf")]
+        Format::Protobuf => {
+            let mut body = Vec::<u8>::new();
+            ProtobufEncoder::new().encode(families, &mut body)?;
+
+            Ok(Rendered {
+                content_type: PROTOBUF_CONTENT_TYPE,
+                body,
+            })
+        }
+    }
+}
+
+/// Encodes the metric families in the OpenMetrics text format.
+///
+/// The metric model has no created timestamps or exemplars, so no `_created` samples or
+/// exemplars are written.
+fn encode_openmetrics(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+
+    for family in families.iter().filter(|f| !f.get_metric().is_empty()) {
+        let (name, kind) = match family.get_field_type() {
+            MetricType::COUNTER => (
+                family
+                    .get_name()
+                    .strip_suffix("_total")
+                    .unwrap_or_else(|| family.get_name()),
+                "counter",
+            ),
+            MetricType::GAUGE => (family.get_name(), "gauge"),
+            MetricType::HISTOGRAM => (family.get_name(), "histogram"),
+            MetricType::SUMMARY => (family.get_name(), "summary"),
+            MetricType::UNTYPED => (family.get_name(), "unknown"),
+        };
+
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        if !family.get_help().is_empty() {
+            let _ = writeln!(out, "# HELP {} {}", name, escape(family.get_help()));
+        }
+
+        for metric in family.get_metric() {
+            match family.get_field_type() {
+                MetricType::COUNTER => {
+                    let value = metric.get_counter().get_value();
+                    write_sample(&mut out, name, "_total", metric, None, value);
+                }
+                MetricType::GAUGE => {
+                    let value = metric.get_gauge().get_value();
+                    write_sample(&mut out, name, "", metric, None, value);
+                }
+                MetricType::UNTYPED => {
+                    // Untyped metrics are deprecated, but custom collectors may still emit them
+                    #[allow(deprecated)]
+                    let value = metric.get_untyped().get_value();
+                    write_sample(&mut out, name, "", metric, None, value);
+                }
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let mut has_inf = false;
+
+                    for bucket in histogram.get_bucket() {
+                        let upper_bound = bucket.get_upper_bound();
+                        has_inf |= upper_bound == f64::INFINITY;
+
+                        let le = ("le", format_bound(upper_bound));
+                        let count = bucket.get_cumulative_count() as f64;
+                        write_sample(&mut out, name, "_bucket", metric, Some(le), count);
+                    }
+
+                    // OpenMetrics requires the +Inf bucket, which the model leaves implicit
+                    if !has_inf {
+                        let le = ("le", "+Inf".to_owned());
+                        let count = histogram.get_sample_count() as f64;
+                        write_sample(&mut out, name, "_bucket", metric, Some(le), count);
+                    }
+
+                    let count = histogram.get_sample_count() as f64;
+                    write_sample(&mut out, name, "_count", metric, None, count);
+                    let sum = histogram.get_sample_sum();
+                    write_sample(&mut out, name, "_sum", metric, None, sum);
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+
+                    for quantile in summary.get_quantile() {
+                        let q = ("quantile", format_bound(quantile.get_quantile()));
+                        let value = quantile.get_value();
+                        write_sample(&mut out, name, "", metric, Some(q), value);
+                    }
+
+                    let count = summary.get_sample_count() as f64;
+                    write_sample(&mut out, name, "_count", metric, None, count);
+                    let sum = summary.get_sample_sum();
+                    write_sample(&mut out, name, "_sum", metric, None, sum);
+                }
+            }
+        }
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    suffix: &str,
+    metric: &Metric,
+    extra_label: Option<(&str, String)>,
+    value: f64,
+) {
+    out.push_str(name);
+    out.push_str(suffix);
+
+    let labels = metric
+        .get_label()
+        .iter()
+        .map(|label: &LabelPair| (label.get_name(), escape(label.get_value())))
+        .chain(extra_label)
+        .map(|(name, value)| format!("{}=\"{}\"", name, value))
+        .collect::<Vec<_>>();
+
+    if !labels.is_empty() {
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+
+    let _ = write!(out, " {}", format_value(value));
+
+    // OpenMetrics timestamps are in seconds
+    if metric.get_timestamp_ms() != 0 {
+        let _ = write!(out, " {}", metric.get_timestamp_ms() as f64 / 1000.0);
+    }
+
+    out.push('\n');
+}
+
+fn format_value(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_owned()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_owned()
+    } else if value.is_nan() {
+        "NaN".to_owned()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats `le` and `quantile` label values, which OpenMetrics requires to be canonical floats.
+fn format_bound(value: f64) -> String {
+    if value.is_finite() && value.fract() == 0.0 {
+        format!("{:.1}", value)
+    } else {
+        format_value(value)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_openmetrics, Format};
+
+    use prometheus::{CounterVec, Gauge, Histogram, HistogramOpts, Opts, Registry};
+
+    #[test]
+    fn negotiate_defaults_to_text() {
+        assert_eq!(Format::negotiate(None), Format::Text);
+        assert_eq!(Format::negotiate(Some("")), Format::Text);
+        assert_eq!(Format::negotiate(Some("application/json")), Format::Text);
+        assert_eq!(Format::negotiate(Some("*/*")), Format::Text);
+    }
+
+    #[test]
+    fn negotiate_openmetrics() {
+        assert_eq!(
+            Format::negotiate(Some("application/openmetrics-text; version=1.0.0")),
+            Format::OpenMetrics
+        );
+        assert_eq!(
+            Format::negotiate(Some("Application/OpenMetrics-Text")),
+            Format::OpenMetrics
+        );
+    }
+
+    #[test]
+    fn negotiate_picks_the_highest_quality() {
+        assert_eq!(
+            Format::negotiate(Some("application/openmetrics-text;q=0.5, text/plain")),
+            Format::Text
+        );
+        assert_eq!(
+            Format::negotiate(Some(
+                "text/plain;q=0.5, application/openmetrics-text;version=1.0.0;q=0.9"
+            )),
+            Format::OpenMetrics
+        );
+        assert_eq!(
+            Format::negotiate(Some("application/openmetrics-text;q=0")),
+            Format::Text
+        );
+    }
+
+    #[test]
+    fn negotiate_prefers_earlier_ranges_on_ties() {
+        assert_eq!(
+            Format::negotiate(Some("application/openmetrics-text, text/plain")),
+            Format::OpenMetrics
+        );
+        assert_eq!(
+            Format::negotiate(Some("text/plain, application/openmetrics-text")),
+            Format::Text
+        );
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn negotiate_protobuf() {
+        assert_eq!(
+            Format::negotiate(Some(
+                "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;\
+                 encoding=delimited;q=0.7,text/plain;version=0.0.4;q=0.3"
+            )),
+            Format::Protobuf
+        );
+        assert_eq!(
+            Format::negotiate(Some(
+                "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;\
+                 encoding=text"
+            )),
+            Format::Text
+        );
+    }
+
+    #[test]
+    fn encode_counter() {
+        let registry = Registry::new();
+        let counter = CounterVec::new(
+            Opts::new("requests_total", "Requests \"served\"."),
+            &["path"],
+        )
+        .unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter.with_label_values(&["/a\nb"]).inc_by(3.0);
+
+        assert_eq!(
+            encode_openmetrics(&registry.gather()),
+            "# TYPE requests counter\n\
+             # HELP requests Requests \\\"served\\\".\n\
+             requests_total{path=\"/a\\nb\"} 3\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn encode_gauge() {
+        let registry = Registry::new();
+        let gauge = Gauge::new("temperature", "Temperature.").unwrap();
+        registry.register(Box::new(gauge.clone())).unwrap();
+        gauge.set(-1.5);
+
+        assert_eq!(
+            encode_openmetrics(&registry.gather()),
+            "# TYPE temperature gauge\n\
+             # HELP temperature Temperature.\n\
+             temperature -1.5\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn encode_histogram() {
+        let registry = Registry::new();
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("latency_seconds", "Latency.").buckets(vec![0.5, 1.0]),
+        )
+        .unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+        histogram.observe(0.25);
+        histogram.observe(0.75);
+        histogram.observe(2.0);
+
+        assert_eq!(
+            encode_openmetrics(&registry.gather()),
+            "# TYPE latency_seconds histogram\n\
+             # HELP latency_seconds Latency.\n\
+             latency_seconds_bucket{le=\"0.5\"} 1\n\
+             latency_seconds_bucket{le=\"1.0\"} 2\n\
+             latency_seconds_bucket{le=\"+Inf\"} 3\n\
+             latency_seconds_count 3\n\
+             latency_seconds_sum 3\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn encode_nothing() {
+        assert_eq!(encode_openmetrics(&[]), "# EOF\n");
+    }
+}
@@ -1,4 +1,112 @@
+use crate::{Error, Result};
+
 use ops_core::Health;
 
+/// The registered status codes a response with a body can have, which both servers can send.
+const BODY_STATUS_CODES: &[u16] = &[
+    200, 201, 202, 203, 206, 207, 226, 300, 301, 302, 303, 307, 308, 400, 401, 402, 403, 404, 405,
+    406, 407, 408, 409, 410, 411, 412, 413, 414, 415, 416, 417, 418, 421, 422, 423, 424, 425, 426,
+    428, 429, 431, 451, 500, 501, 502, 503, 504, 505, 506, 507, 508, 510, 511,
+];
+
+/// Maps the overall [`Health`](enum.Health.html) to the status code of `/__/health`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HealthStatusCodes {
+    healthy: u16,
+    degraded: u16,
+    unhealthy: u16,
+}
+
+impl Default for HealthStatusCodes {
+    /// Responds with 503 when unhealthy, so that probes fail, and 200 otherwise.
+    fn default() -> Self {
+        Self {
+            healthy: 200,
+            degraded: 200,
+            unhealthy: 503,
+        }
+    }
+}
+
+impl HealthStatusCodes {
+    /// Creates a [`HealthStatusCodes`](struct.HealthStatusCodes.html) with the status code for
+    /// each health.
+    ///
+    /// Fails unless every code is a registered status code that a response with a body can have,
+    /// such as 200 or 503 but not 204 or 304.
+    pub fn new(healthy: u16, degraded: u16, unhealthy: u16) -> Result<Self> {
+        for code in &[healthy, degraded, unhealthy] {
+            if !BODY_STATUS_CODES.contains(code) {
+                return Err(Error::InvalidStatusCode(*code));
+            }
+        }
+
+        Ok(Self {
+            healthy,
+            degraded,
+            unhealthy,
+        })
+    }
+
+    /// The status code for the health.
+    pub fn code(&self, health: Health) -> u16 {
+        match health {
+            Health::Healthy => self.healthy,
+            Health::Degraded => self.degraded,
+            Health::Unhealthy => self.unhealthy,
+        }
+    }
+}
+
 pub(crate) const HEALTH_STATUSES: &[Health; 3] =
     &[Health::Healthy, Health::Degraded, Health::Unhealthy];
+
+/// The group to check from the query of a `/__/health` request, if any.
+///
+/// Group paths are left undecoded, as check names never need percent-encoding.
+pub(crate) fn group_param(query: Option<&str>) -> Option<&str> {
+    query?.split('&').find_map(|pair| {
+        let mut pair = pair.splitn(2, '=');
+
+        match (pair.next(), pair.next()) {
+            (Some("group"), Some(group)) if !group.is_empty() => Some(group),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HealthStatusCodes;
+    use crate::Error;
+
+    use ops_core::Health;
+
+    #[test]
+    fn new_maps_each_health() {
+        let codes = HealthStatusCodes::new(200, 429, 500).unwrap();
+
+        assert_eq!(codes.code(Health::Healthy), 200);
+        assert_eq!(codes.code(Health::Degraded), 429);
+        assert_eq!(codes.code(Health::Unhealthy), 500);
+    }
+
+    #[test]
+    fn new_rejects_codes_the_servers_cannot_send_with_a_body() {
+        for code in &[0, 99, 100, 204, 299, 304, 600, 999] {
+            match HealthStatusCodes::new(200, 200, *code) {
+                Err(Error::InvalidStatusCode(invalid)) => assert_eq!(invalid, *code),
+                other => panic!("{} was not rejected: {:?}", code, other),
+            }
+        }
+    }
+
+    #[test]
+    fn default_fails_when_unhealthy() {
+        let codes = HealthStatusCodes::default();
+
+        assert_eq!(codes.code(Health::Healthy), 200);
+        assert_eq!(codes.code(Health::Degraded), 200);
+        assert_eq!(codes.code(Health::Unhealthy), 503);
+    }
+}
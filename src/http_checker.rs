@@ -0,0 +1,391 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Result};
+
+use futures_timer::Delay;
+use futures_util::future::{self, Either};
+use hyper::body::Bytes;
+use hyper::client::HttpConnector;
+use hyper::{Client, StatusCode, Uri};
+use ops_core::{async_trait, CheckResponse, Checker};
+use serde_json::Value;
+
+/// Checks that a GET request to an HTTP endpoint succeeds.
+///
+/// Only plain `http` URLs are supported.
+pub struct HttpChecker {
+    client: Client<HttpConnector>,
+    uri: Uri,
+    timeout: Duration,
+    expected_statuses: Vec<u16>,
+    assertion: Option<BodyAssertion>,
+    degraded_latency: Option<Duration>,
+    action: String,
+    impact: String,
+}
+
+#[derive(Debug)]
+enum BodyAssertion {
+    Contains(String),
+    JsonPointer(String, Value),
+}
+
+impl fmt::Debug for HttpChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpChecker")
+            .field("uri", &self.uri)
+            .field("timeout", &self.timeout)
+            .field("expected_statuses", &self.expected_statuses)
+            .field("assertion", &self.assertion)
+            .field("degraded_latency", &self.degraded_latency)
+            .finish()
+    }
+}
+
+impl HttpChecker {
+    /// Creates an [`HttpChecker`](struct.HttpChecker.html) for a URL, expecting a `2xx` status
+    /// within 5 seconds.
+    ///
+    /// Fails unless the URL is a plain `http` URL.
+    pub fn new(url: &str) -> Result<Self> {
+        let uri = parse_http_uri(url)?;
+
+        Ok(HttpChecker {
+            client: Client::new(),
+            uri,
+            timeout: Duration::from_secs(5),
+            expected_statuses: Vec::new(),
+            assertion: None,
+            degraded_latency: None,
+            action: format!("Check that {} is reachable", url),
+            impact: format!("Requests to {} are failing", url),
+        })
+    }
+
+    /// Sets the timeout for the whole request, including reading the body.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Adds an expected status code, replacing the default of any `2xx` status.
+    pub fn expected_status(mut self, status: u16) -> Self {
+        self.expected_statuses.push(status);
+        self
+    }
+
+    /// Expects the response body to contain the text.
+    pub fn body_contains(mut self, text: &str) -> Self {
+        self.assertion = Some(BodyAssertion::Contains(text.to_owned()));
+        self
+    }
+
+    /// Expects the response body to be JSON with the value at the
+    /// [JSON pointer](https://tools.ietf.org/html/rfc6901) equal to `expected`.
+    pub fn json_pointer(mut self, pointer: &str, expected: Value) -> Self {
+        self.assertion = Some(BodyAssertion::JsonPointer(pointer.to_owned(), expected));
+        self
+    }
+
+    /// Reports the check as degraded when the request takes at least this long.
+    pub fn degraded_latency(mut self, latency: Duration) -> Self {
+        self.degraded_latency = Some(latency);
+        self
+    }
+
+    /// Sets the action reported when the check is not healthy.
+    pub fn action(mut self, action: &str) -> Self {
+        self.action = action.to_owned();
+        self
+    }
+
+    /// Sets the impact reported when the check is unhealthy.
+    pub fn impact(mut self, impact: &str) -> Self {
+        self.impact = impact.to_owned();
+        self
+    }
+
+    fn is_expected(&self, status: StatusCode) -> bool {
+        if self.expected_statuses.is_empty() {
+            status.is_success()
+        } else {
+            self.expected_statuses.contains(&status.as_u16())
+        }
+    }
+
+    fn assert_body(&self, body: &[u8]) -> std::result::Result<(), String> {
+        match &self.assertion {
+            None => Ok(()),
+            Some(BodyAssertion::Contains(text)) => {
+                if String::from_utf8_lossy(body).contains(text.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("body does not contain {:?}", text))
+                }
+            }
+            Some(BodyAssertion::JsonPointer(pointer, expected)) => {
+                let json = serde_json::from_slice::<Value>(body)
+                    .map_err(|err| format!("body is not JSON: {}", err))?;
+
+                match json.pointer(pointer) {
+                    Some(value) if value == expected => Ok(()),
+                    Some(value) => Err(format!("{} is {}, expected {}", pointer, value, expected)),
+                    None => Err(format!("{} is missing, expected {}", pointer, expected)),
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Checker for HttpChecker {
+    async fn check(&self) -> CheckResponse {
+        let (status, body, latency) = match get(&self.client, &self.uri, self.timeout).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                return CheckResponse::unhealthy(
+                    &format!("GET {} failed: {}", self.uri, err),
+                    &self.action,
+                    &self.impact,
+                )
+            }
+        };
+
+        let output = format!(
+            "GET {} returned {} in {:.3}ms",
+            self.uri,
+            status,
+            latency.as_secs_f64() * 1000.0
+        );
+
+        if !self.is_expected(status) {
+            return CheckResponse::unhealthy(
+                &format!("{}, which was not expected", output),
+                &self.action,
+                &self.impact,
+            );
+        }
+
+        if let Err(err) = self.assert_body(&body) {
+            return CheckResponse::unhealthy(
+                &format!("{}, but {}", output, err),
+                &self.action,
+                &self.impact,
+            );
+        }
+
+        if self.degraded_latency.map_or(false, |max| latency >= max) {
+            CheckResponse::degraded(&output, &self.action)
+        } else {
+            CheckResponse::healthy(&output)
+        }
+    }
+}
+
+/// Parses a URL, which must be a plain `http` URL as there is no TLS support.
+pub(crate) fn parse_http_uri(url: &str) -> Result<Uri> {
+    let uri = url.parse::<Uri>().map_err(hyper::http::Error::from)?;
+
+    if uri.scheme_str() == Some("http") {
+        Ok(uri)
+    } else {
+        Err(Error::UnsupportedScheme(url.to_owned()))
+    }
+}
+
+/// Sends a GET request and reads the whole body, returning the status, body and latency.
+pub(crate) async fn get(
+    client: &Client<HttpConnector>,
+    uri: &Uri,
+    timeout: Duration,
+) -> std::result::Result<(StatusCode, Bytes, Duration), String> {
+    let start = Instant::now();
+
+    let request = Box::pin(async {
+        let resp = client.get(uri.clone()).await?;
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await?;
+
+        Ok::<_, hyper::Error>((status, body))
+    });
+
+    match future::select(request, Delay::new(timeout)).await {
+        Either::Left((Ok((status, body)), _)) => Ok((status, body, start.elapsed())),
+        Either::Left((Err(err), _)) => Err(err.to_string()),
+        Either::Right(_) => Err(format!(
+            "timed out after {:.3}s",
+            start.elapsed().as_secs_f64()
+        )),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    use super::HttpChecker;
+    use crate::Error;
+
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+    use ops_core::{Checker, Health};
+    use serde_json::json;
+
+    /// Serves the response to every request after the delay, returning the base URL.
+    pub(crate) fn serve(status: u16, body: &'static str, delay: Duration) -> String {
+        let make_svc = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |_| async move {
+                tokio::time::sleep(delay).await;
+
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+            }))
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        url
+    }
+
+    #[test]
+    fn only_http_urls_are_supported() {
+        for url in &["https://example.com", "ftp://example.com", "example.com:80"] {
+            match HttpChecker::new(url) {
+                Err(Error::UnsupportedScheme(unsupported)) => assert_eq!(unsupported, *url),
+                other => panic!("{} was not rejected: {:?}", url, other),
+            }
+        }
+
+        assert!(HttpChecker::new("http://example.com/health").is_ok());
+    }
+
+    #[tokio::test]
+    async fn success_status_is_healthy() {
+        let url = serve(200, "ok", Duration::ZERO);
+
+        let resp = HttpChecker::new(&url).unwrap().check().await;
+
+        assert_eq!(resp.health(), Health::Healthy);
+        assert!(resp.output().contains("returned 200 OK"));
+    }
+
+    #[tokio::test]
+    async fn error_status_is_unhealthy() {
+        let url = serve(500, "", Duration::ZERO);
+
+        let resp = HttpChecker::new(&url).unwrap().check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp.output().contains("which was not expected"));
+    }
+
+    #[tokio::test]
+    async fn expected_status_replaces_success() {
+        let url = serve(404, "", Duration::ZERO);
+        let checker = HttpChecker::new(&url).unwrap().expected_status(404);
+
+        assert_eq!(checker.check().await.health(), Health::Healthy);
+
+        let url = serve(200, "", Duration::ZERO);
+        let checker = HttpChecker::new(&url).unwrap().expected_status(404);
+
+        assert_eq!(checker.check().await.health(), Health::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn body_contains() {
+        let url = serve(200, "status: all good", Duration::ZERO);
+
+        let checker = HttpChecker::new(&url).unwrap().body_contains("all good");
+        assert_eq!(checker.check().await.health(), Health::Healthy);
+
+        let checker = HttpChecker::new(&url).unwrap().body_contains("all bad");
+        let resp = checker.check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp
+            .output()
+            .ends_with("but body does not contain \"all bad\""));
+    }
+
+    #[tokio::test]
+    async fn json_pointer() {
+        let url = serve(200, r#"{"db":{"status":"up"}}"#, Duration::ZERO);
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .json_pointer("/db/status", json!("up"));
+        assert_eq!(checker.check().await.health(), Health::Healthy);
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .json_pointer("/db/status", json!("down"));
+        let resp = checker.check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp
+            .output()
+            .ends_with(r#"but /db/status is "up", expected "down""#));
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .json_pointer("/cache/status", json!("up"));
+        let resp = checker.check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp
+            .output()
+            .ends_with(r#"but /cache/status is missing, expected "up""#));
+    }
+
+    #[tokio::test]
+    async fn json_pointer_on_invalid_json() {
+        let url = serve(200, "not json", Duration::ZERO);
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .json_pointer("/status", json!("up"));
+        let resp = checker.check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp.output().contains("but body is not JSON"));
+    }
+
+    #[tokio::test]
+    async fn slow_response_is_degraded() {
+        let url = serve(200, "ok", Duration::from_millis(100));
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .degraded_latency(Duration::from_millis(50));
+
+        assert_eq!(checker.check().await.health(), Health::Degraded);
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .degraded_latency(Duration::from_secs(5));
+
+        assert_eq!(checker.check().await.health(), Health::Healthy);
+    }
+
+    #[tokio::test]
+    async fn timeout_is_unhealthy() {
+        let url = serve(200, "ok", Duration::from_secs(5));
+
+        let checker = HttpChecker::new(&url)
+            .unwrap()
+            .timeout(Duration::from_millis(50));
+        let resp = checker.check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert!(resp.output().contains("timed out after"));
+    }
+}
@@ -8,23 +8,59 @@
     unreachable_pub
 )]
 
+mod about;
+mod aggregation;
+mod build_info;
 mod check;
+mod compression;
+#[cfg(unix)]
+mod disk_checker;
 mod error;
+mod exposition;
 mod health;
+#[cfg(feature = "http_checker")]
+mod http_checker;
+mod liveness;
+mod metrics;
+#[cfg(target_os = "linux")]
+mod process_checker;
+mod readiness;
+mod registry;
+mod report;
+mod runner;
 #[cfg(feature = "hyper_server")]
 mod server;
 mod status;
 #[cfg(feature = "trillium_server")]
 mod trillium;
+#[cfg(feature = "http_checker")]
+mod upstream_checker;
 
+pub use crate::about::{About, Link, Owner};
+pub use crate::aggregation::{Aggregation, CheckHealth};
+pub use crate::build_info::{emit_build_env, BuildInfo};
 pub use crate::check::NamedChecker;
+#[cfg(unix)]
+pub use crate::disk_checker::{DiskChecker, Threshold};
 pub use crate::error::Error;
+pub use crate::health::HealthStatusCodes;
+#[cfg(feature = "http_checker")]
+pub use crate::http_checker::HttpChecker;
+pub use crate::liveness::Heartbeat;
+#[cfg(target_os = "linux")]
+pub use crate::process_checker::ProcessChecker;
+pub use crate::readiness::ReadinessHandle;
+pub use crate::registry::CheckRegistry;
+pub use crate::report::{HealthReport, HealthReportEntry};
+pub use crate::runner::CheckRunner;
 #[cfg(feature = "hyper_server")]
-pub use crate::server::server;
+pub use crate::server::{server, server_with_shutdown};
 pub use crate::status::{StatusBuilder, StatusNoChecks, StatusWithChecks};
 #[cfg(feature = "trillium_server")]
 pub use crate::trillium::router;
-pub use ops_core::{async_trait, CheckResponse, Checker};
+#[cfg(feature = "http_checker")]
+pub use crate::upstream_checker::UpstreamChecker;
+pub use ops_core::{async_trait, CheckResponse, Checker, Health, TcpChecker};
 
 /// Result type often returned from methods that can have ops `Error`s.
 pub type Result<T> = ::std::result::Result<T, Error>;
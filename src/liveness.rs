@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_timer::Delay;
+
+/// Detects a deadlocked event loop, by a heartbeat that is only sent while the loop is serviced.
+///
+/// Cloned heartbeats share the same state.
+#[derive(Clone, Debug)]
+pub struct Heartbeat {
+    start: Instant,
+    last_beat_millis: Arc<AtomicU64>,
+    max_age: Duration,
+}
+
+impl Heartbeat {
+    /// Creates a [`Heartbeat`](struct.Heartbeat.html) that is considered dead once no beat has
+    /// been sent for `max_age`.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            last_beat_millis: Arc::new(AtomicU64::new(0)),
+            max_age,
+        }
+    }
+
+    /// Sends a beat.
+    pub fn beat(&self) {
+        self.last_beat_millis
+            .store(self.start.elapsed().as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Sends a beat on every interval, forever.
+    ///
+    /// This should be spawned onto the event loop that is monitored, with an interval well
+    /// below the maximum age.
+    pub async fn run(self, interval: Duration) {
+        loop {
+            self.beat();
+            Delay::new(interval).await;
+        }
+    }
+
+    /// Whether a beat has been sent within the maximum age.
+    pub fn is_alive(&self) -> bool {
+        let last_beat = Duration::from_millis(self.last_beat_millis.load(Ordering::SeqCst));
+
+        self.start.elapsed().saturating_sub(last_beat) < self.max_age
+    }
+}
@@ -0,0 +1,341 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::check::NamedChecker;
+use crate::health::HEALTH_STATUSES;
+use crate::Result;
+
+use once_cell::sync::Lazy;
+use ops_core::CheckResponse;
+use prometheus::proto::MetricFamily;
+use prometheus::{histogram_opts, opts, GaugeVec, HistogramVec, IntCounterVec, Registry};
+
+const HEALTHCHECK_NAME: &str = "healthcheck_name";
+const HEALTHCHECK_GROUP: &str = "healthcheck_group";
+const HEALTHCHECK_RESULT: &str = "healthcheck_result";
+const HEALTHCHECK_STATUS: &str = "healthcheck_status";
+const HEALTHCHECK_PANICS: &str = "healthcheck_panics_total";
+const HEALTHCHECK_DURATION: &str = "healthcheck_duration_seconds";
+const HEALTHCHECK_LAST_RUN: &str = "healthcheck_last_run_timestamp_seconds";
+const HEALTHCHECK_RUNS: &str = "healthcheck_runs_total";
+
+static DEFAULT_CHECK_METRICS: Lazy<CheckMetrics> = Lazy::new(|| {
+    let metrics = CheckMetrics::new().expect("the check metrics are valid");
+
+    // A clash with metrics already in the default registry leaves the check metrics unexported,
+    // rather than failing every status, a custom registry reports the error instead
+    if let Err(err) = metrics.register(prometheus::default_registry()) {
+        log::error!(
+            "the check metrics are not exported, as registering them into the default registry \
+             failed: {}",
+            err
+        );
+    }
+
+    metrics
+});
+
+static DETACHED_CHECK_METRICS: Lazy<CheckMetrics> =
+    Lazy::new(|| CheckMetrics::new().expect("the check metrics are valid"));
+
+/// The metrics updated by running checks.
+#[derive(Clone, Debug)]
+pub(crate) struct CheckMetrics {
+    status: GaugeVec,
+    panics: IntCounterVec,
+    duration: HistogramVec,
+    last_run: GaugeVec,
+    runs: IntCounterVec,
+}
+
+impl Default for CheckMetrics {
+    /// The check metrics registered into the default registry, shared by every status.
+    fn default() -> Self {
+        DEFAULT_CHECK_METRICS.clone()
+    }
+}
+
+impl CheckMetrics {
+    /// Creates check metrics registered into the registry.
+    pub(crate) fn registered(registry: &Registry) -> Result<Self> {
+        let metrics = Self::new()?;
+        metrics.register(registry)?;
+        Ok(metrics)
+    }
+
+    /// Check metrics registered nowhere, for checks run outside of a status.
+    pub(crate) fn detached() -> Self {
+        DETACHED_CHECK_METRICS.clone()
+    }
+
+    fn new() -> Result<Self> {
+        Ok(Self {
+            status: GaugeVec::new(
+                opts!(
+                    HEALTHCHECK_STATUS,
+                    "Meters the healthcheck status based for each check and for each result"
+                ),
+                &[HEALTHCHECK_NAME, HEALTHCHECK_GROUP, HEALTHCHECK_RESULT],
+            )?,
+            panics: IntCounterVec::new(
+                opts!(HEALTHCHECK_PANICS, "Counts the panics of each check"),
+                &[HEALTHCHECK_NAME, HEALTHCHECK_GROUP],
+            )?,
+            duration: HistogramVec::new(
+                histogram_opts!(
+                    HEALTHCHECK_DURATION,
+                    "Meters the duration of each check for each result"
+                ),
+                &[HEALTHCHECK_NAME, HEALTHCHECK_GROUP, HEALTHCHECK_RESULT],
+            )?,
+            last_run: GaugeVec::new(
+                opts!(
+                    HEALTHCHECK_LAST_RUN,
+                    "The unix timestamp of the last run of each check"
+                ),
+                &[HEALTHCHECK_NAME, HEALTHCHECK_GROUP],
+            )?,
+            runs: IntCounterVec::new(
+                opts!(HEALTHCHECK_RUNS, "Counts the runs of each check by result"),
+                &[HEALTHCHECK_NAME, HEALTHCHECK_GROUP, HEALTHCHECK_RESULT],
+            )?,
+        })
+    }
+
+    fn register(&self, registry: &Registry) -> Result<()> {
+        registry.register(Box::new(self.status.clone()))?;
+        registry.register(Box::new(self.panics.clone()))?;
+        registry.register(Box::new(self.duration.clone()))?;
+        registry.register(Box::new(self.last_run.clone()))?;
+        registry.register(Box::new(self.runs.clone()))?;
+        Ok(())
+    }
+
+    /// Updates the metrics of a check, `group` being the path of the group it belongs to.
+    pub(crate) fn update(
+        &self,
+        checker: &NamedChecker,
+        group: &str,
+        response: &CheckResponse,
+        duration: Duration,
+    ) {
+        let res = response.health();
+        let result: &'static str = res.into();
+
+        self.duration
+            .with_label_values(&[checker.name(), group, result])
+            .observe(duration.as_secs_f64());
+        self.runs
+            .with_label_values(&[checker.name(), group, result])
+            .inc();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.last_run
+            .with_label_values(&[checker.name(), group])
+            .set(now.as_secs_f64());
+
+        // Every result is exported, so that series of earlier results are reset to 0
+        for health in HEALTH_STATUSES {
+            let value = if *health == res { 1.0 } else { 0.0 };
+
+            self.status
+                .with_label_values(&[checker.name(), group, (*health).into()])
+                .set(value);
+        }
+    }
+
+    pub(crate) fn panicked(&self, checker: &NamedChecker, group: &str) {
+        self.panics
+            .with_label_values(&[checker.name(), group])
+            .inc();
+    }
+
+    /// Deletes the series of a check that has been removed, along with those of its group.
+    pub(crate) fn remove(&self, checker: &NamedChecker, group: &str) {
+        // Deleting a series that was never exported fails, which is fine to ignore
+        for health in HEALTH_STATUSES {
+            let labels = [checker.name(), group, (*health).into()];
+
+            let _ = self.status.remove_label_values(&labels);
+            let _ = self.duration.remove_label_values(&labels);
+            let _ = self.runs.remove_label_values(&labels);
+        }
+
+        let _ = self.last_run.remove_label_values(&[checker.name(), group]);
+        let _ = self.panics.remove_label_values(&[checker.name(), group]);
+
+        let path = group_path(group, checker.name());
+        for child in checker.checkers().unwrap_or_default() {
+            self.remove(child, &path);
+        }
+    }
+}
+
+/// The path of the checkers of a group, nested groups being separated by dots.
+pub(crate) fn group_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+/// Gathers the metrics of the registries, or of the default registry if there are none.
+///
+/// Families with the same name in several registries are merged into one, as the exposition
+/// format allows a name only once, a family whose type differs from the first is dropped.
+pub(crate) fn gather(registries: &[Registry]) -> Vec<MetricFamily> {
+    if registries.is_empty() {
+        return prometheus::gather();
+    }
+
+    let mut families: Vec<MetricFamily> = Vec::new();
+    for mut family in registries.iter().flat_map(Registry::gather) {
+        match families
+            .iter_mut()
+            .find(|f| f.get_name() == family.get_name())
+        {
+            Some(existing) if existing.get_field_type() == family.get_field_type() => {
+                existing.mut_metric().extend(family.take_metric());
+            }
+            Some(_) => log::error!(
+                "the {} metrics are not exported, as another registry has them with another type",
+                family.get_name()
+            ),
+            None => families.push(family),
+        }
+    }
+    families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    families
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::status::Status;
+    use crate::StatusBuilder;
+    use ops_core::{async_trait, Checker};
+    use prometheus::{IntCounter, IntGauge};
+
+    /// Unhealthy on the first check, healthy after.
+    struct Recovers(AtomicBool);
+
+    #[async_trait]
+    impl Checker for Recovers {
+        async fn check(&self) -> CheckResponse {
+            if self.0.swap(true, Ordering::SeqCst) {
+                CheckResponse::healthy("recovered")
+            } else {
+                CheckResponse::unhealthy("down", "wait", "none")
+            }
+        }
+    }
+
+    /// The `healthcheck_status` series of the check, by result.
+    fn statuses(registry: &Registry, name: &str) -> Vec<(String, f64)> {
+        let mut statuses: Vec<_> = registry
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == "healthcheck_status")
+            .flat_map(|family| family.get_metric())
+            .filter(|metric| {
+                metric.get_label().iter().any(|label| {
+                    label.get_name() == "healthcheck_name" && label.get_value() == name
+                })
+            })
+            .map(|metric| {
+                let result = metric
+                    .get_label()
+                    .iter()
+                    .find(|label| label.get_name() == "healthcheck_result")
+                    .map(|label| label.get_value().to_owned())
+                    .unwrap_or_default();
+
+                (result, metric.get_gauge().get_value())
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        statuses
+    }
+
+    #[tokio::test]
+    async fn earlier_results_are_reset() {
+        let registry = Registry::new();
+        let status = StatusBuilder::healthchecks("app", "")
+            .prometheus_registry(registry.clone())
+            .unwrap()
+            .checker(NamedChecker::new(
+                "recovers",
+                Box::new(Recovers(AtomicBool::new(false))),
+            ));
+
+        status.check().await;
+
+        assert_eq!(
+            statuses(&registry, "recovers"),
+            [
+                ("degraded".to_owned(), 0.0),
+                ("healthy".to_owned(), 0.0),
+                ("unhealthy".to_owned(), 1.0),
+            ]
+        );
+
+        status.check().await;
+
+        assert_eq!(
+            statuses(&registry, "recovers"),
+            [
+                ("degraded".to_owned(), 0.0),
+                ("healthy".to_owned(), 1.0),
+                ("unhealthy".to_owned(), 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn families_are_merged_across_registries() {
+        let first = Registry::new();
+        let second = Registry::new();
+        let requests =
+            IntCounterVec::new(opts!("requests_total", "Requests"), &["registry"]).unwrap();
+        first.register(Box::new(requests.clone())).unwrap();
+        requests.with_label_values(&["first"]).inc();
+        let other = IntCounterVec::new(opts!("requests_total", "Requests"), &["registry"]).unwrap();
+        second.register(Box::new(other.clone())).unwrap();
+        other.with_label_values(&["second"]).inc();
+        second
+            .register(Box::new(IntCounter::new("errors_total", "Errors").unwrap()))
+            .unwrap();
+
+        let families = gather(&[first, second]);
+
+        let names: Vec<_> = families.iter().map(MetricFamily::get_name).collect();
+        assert_eq!(names, ["errors_total", "requests_total"]);
+        assert_eq!(families[1].get_metric().len(), 2);
+    }
+
+    #[test]
+    fn families_with_another_type_are_dropped() {
+        let first = Registry::new();
+        let second = Registry::new();
+        first
+            .register(Box::new(IntCounter::new("requests", "Requests").unwrap()))
+            .unwrap();
+        second
+            .register(Box::new(IntGauge::new("requests", "Requests").unwrap()))
+            .unwrap();
+
+        let families = gather(&[first, second]);
+
+        assert_eq!(families.len(), 1);
+        assert_eq!(
+            families[0].get_field_type(),
+            prometheus::proto::MetricType::COUNTER
+        );
+        assert_eq!(families[0].get_metric().len(), 1);
+    }
+}
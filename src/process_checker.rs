@@ -0,0 +1,275 @@
+use std::fs;
+
+use crate::disk_checker::format_bytes;
+
+use ops_core::{async_trait, CheckResponse, Checker, Health};
+use prometheus::core::Collector;
+use prometheus::process_collector::ProcessCollector;
+use prometheus::proto::MetricFamily;
+
+/// The limit a resource is compared against.
+#[derive(Clone, Copy, Debug)]
+enum Budget {
+    ProcessLimit,
+    Absolute(u64),
+}
+
+/// Checks the resident memory, open file descriptors and threads of the process against a budget.
+///
+/// By default resident memory is compared against the memory limit of the cgroup of the process,
+/// and open file descriptors against the `Max open files` limit in `/proc/self/limits`. Threads
+/// are only checked against a [`thread_budget`](#method.thread_budget), as the only process limit
+/// on them, `Max processes`, counts every process and thread of the user. Resources without a
+/// limit are not checked.
+#[derive(Debug)]
+pub struct ProcessChecker {
+    memory: Budget,
+    fds: Budget,
+    threads: Option<u64>,
+    degraded_ratio: f64,
+    unhealthy_ratio: f64,
+}
+
+impl Default for ProcessChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessChecker {
+    /// Creates a [`ProcessChecker`](struct.ProcessChecker.html) which is degraded at 80% and
+    /// unhealthy at 95% of any limit.
+    pub fn new() -> Self {
+        ProcessChecker {
+            memory: Budget::ProcessLimit,
+            fds: Budget::ProcessLimit,
+            threads: None,
+            degraded_ratio: 0.8,
+            unhealthy_ratio: 0.95,
+        }
+    }
+
+    /// Compares resident memory against a budget in bytes, rather than the cgroup limit.
+    pub fn memory_budget(mut self, bytes: u64) -> Self {
+        self.memory = Budget::Absolute(bytes);
+        self
+    }
+
+    /// Compares open file descriptors against a budget, rather than the process limit.
+    pub fn fd_budget(mut self, fds: u64) -> Self {
+        self.fds = Budget::Absolute(fds);
+        self
+    }
+
+    /// Compares the thread count against a budget, threads are not checked without one.
+    pub fn thread_budget(mut self, threads: u64) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Reports the check as degraded when usage reaches this share, between 0 and 1, of a limit.
+    pub fn degraded_at(mut self, ratio: f64) -> Self {
+        self.degraded_ratio = ratio;
+        self
+    }
+
+    /// Reports the check as unhealthy when usage reaches this share, between 0 and 1, of a limit.
+    pub fn unhealthy_at(mut self, ratio: f64) -> Self {
+        self.unhealthy_ratio = ratio;
+        self
+    }
+
+    fn health(&self, usage: u64, limit: Option<u64>) -> Health {
+        let ratio = match limit {
+            Some(limit) if limit > 0 => usage as f64 / limit as f64,
+            _ => return Health::Healthy,
+        };
+
+        if ratio >= self.unhealthy_ratio {
+            Health::Unhealthy
+        } else if ratio >= self.degraded_ratio {
+            Health::Degraded
+        } else {
+            Health::Healthy
+        }
+    }
+}
+
+#[async_trait]
+impl Checker for ProcessChecker {
+    async fn check(&self) -> CheckResponse {
+        let families = ProcessCollector::for_self().collect();
+        let limits = fs::read_to_string("/proc/self/limits").unwrap_or_default();
+
+        let (memory, fds, threads) = match (
+            gauge_value(&families, "process_resident_memory_bytes"),
+            gauge_value(&families, "process_open_fds"),
+            thread_count(),
+        ) {
+            (Some(memory), Some(fds), Some(threads)) => (memory, fds, threads),
+            _ => {
+                return CheckResponse::unhealthy(
+                    "failed to read the resource usage of the process",
+                    "Check that /proc is mounted",
+                    "Resource leaks in the process cannot be detected",
+                )
+            }
+        };
+
+        let memory_limit = match self.memory {
+            // Linux does not enforce the Max resident set limit, so only the cgroup is used
+            Budget::ProcessLimit => cgroup_memory_limit(),
+            Budget::Absolute(budget) => Some(budget),
+        };
+        let fd_limit = match self.fds {
+            Budget::ProcessLimit => process_limit(&limits, "Max open files"),
+            Budget::Absolute(budget) => Some(budget),
+        };
+        let thread_limit = self.threads;
+
+        let resources = [
+            (
+                self.health(memory, memory_limit),
+                format!(
+                    "resident memory is {} of {}",
+                    format_bytes(memory),
+                    memory_limit.map_or_else(|| "no limit".to_owned(), format_bytes)
+                ),
+                "Investigate the memory usage of the process",
+            ),
+            (
+                self.health(fds, fd_limit),
+                format!(
+                    "{} of {} file descriptors are open",
+                    fds,
+                    format_limit(fd_limit)
+                ),
+                "Investigate the open file descriptors of the process",
+            ),
+            (
+                self.health(threads, thread_limit),
+                match thread_limit {
+                    Some(limit) => format!("{} of {} threads are running", threads, limit),
+                    None => format!("{} threads are running", threads),
+                },
+                "Investigate the threads of the process",
+            ),
+        ];
+
+        let output = resources
+            .iter()
+            .map(|(_, output, _)| output.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let (health, _, action) = resources
+            .iter()
+            .max_by_key(|(health, _, _)| *health)
+            .expect("there are always resources");
+
+        match health {
+            Health::Healthy => CheckResponse::healthy(&output),
+            Health::Degraded => CheckResponse::degraded(&output, action),
+            Health::Unhealthy => CheckResponse::unhealthy(
+                &output,
+                action,
+                "The process is about to run out of resources and fail",
+            ),
+        }
+    }
+}
+
+fn gauge_value(families: &[MetricFamily], name: &str) -> Option<u64> {
+    families
+        .iter()
+        .find(|family| family.get_name() == name)
+        .and_then(|family| family.get_metric().first())
+        .map(|metric| metric.get_gauge().get_value() as u64)
+}
+
+fn thread_count() -> Option<u64> {
+    fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|threads| threads.trim().parse().ok())
+}
+
+/// Reads the soft limit from the contents of `/proc/self/limits`, `None` when unlimited.
+fn process_limit(limits: &str, name: &str) -> Option<u64> {
+    limits
+        .lines()
+        .find_map(|line| line.strip_prefix(name))
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|soft| soft.parse().ok())
+}
+
+/// Reads the memory limit of the cgroup v2 or v1 of the process, `None` when unlimited.
+fn cgroup_memory_limit() -> Option<u64> {
+    fs::read_to_string("/sys/fs/cgroup/memory.max")
+        .or_else(|_| fs::read_to_string("/sys/fs/cgroup/memory/memory.limit_in_bytes"))
+        .ok()
+        .and_then(|limit| limit.trim().parse().ok())
+        // cgroup v1 reports no limit as a page-aligned value close to i64::MAX
+        .filter(|limit| *limit < 1 << 62)
+}
+
+fn format_limit(limit: Option<u64>) -> String {
+    limit.map_or_else(|| "no limit".to_owned(), |limit| limit.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{process_limit, ProcessChecker};
+
+    use ops_core::Health;
+
+    const LIMITS: &str = "\
+Limit                     Soft Limit           Hard Limit           Units     
+Max cpu time              unlimited            unlimited            seconds   
+Max processes             63431                63431                processes 
+Max open files            1024                 524288               files     
+Max resident set          unlimited            unlimited            bytes     
+";
+
+    #[test]
+    fn reads_the_soft_limit() {
+        assert_eq!(process_limit(LIMITS, "Max open files"), Some(1024));
+        assert_eq!(process_limit(LIMITS, "Max processes"), Some(63431));
+    }
+
+    #[test]
+    fn unlimited_and_missing_limits_are_none() {
+        assert_eq!(process_limit(LIMITS, "Max resident set"), None);
+        assert_eq!(process_limit(LIMITS, "Max locked memory"), None);
+        assert_eq!(process_limit("", "Max open files"), None);
+    }
+
+    #[test]
+    fn health_thresholds() {
+        let checker = ProcessChecker::new();
+
+        assert_eq!(checker.health(79, Some(100)), Health::Healthy);
+        assert_eq!(checker.health(80, Some(100)), Health::Degraded);
+        assert_eq!(checker.health(94, Some(100)), Health::Degraded);
+        assert_eq!(checker.health(95, Some(100)), Health::Unhealthy);
+        assert_eq!(checker.health(200, Some(100)), Health::Unhealthy);
+    }
+
+    #[test]
+    fn custom_health_thresholds() {
+        let checker = ProcessChecker::new().degraded_at(0.5).unhealthy_at(0.75);
+
+        assert_eq!(checker.health(49, Some(100)), Health::Healthy);
+        assert_eq!(checker.health(50, Some(100)), Health::Degraded);
+        assert_eq!(checker.health(75, Some(100)), Health::Unhealthy);
+    }
+
+    #[test]
+    fn resources_without_a_limit_are_healthy() {
+        let checker = ProcessChecker::new();
+
+        assert_eq!(checker.health(u64::MAX, None), Health::Healthy);
+        assert_eq!(checker.health(10, Some(0)), Health::Healthy);
+    }
+}
@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Marks the application as ready or not ready at runtime.
+///
+/// Cloned handles share the same readiness.
+#[derive(Clone, Debug)]
+pub struct ReadinessHandle {
+    ready: Arc<AtomicBool>,
+}
+
+impl ReadinessHandle {
+    pub(crate) fn new(ready: bool) -> Self {
+        Self {
+            ready: Arc::new(AtomicBool::new(ready)),
+        }
+    }
+
+    /// Marks the application as ready.
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::SeqCst);
+    }
+
+    /// Marks the application as not ready.
+    pub fn set_not_ready(&self) {
+        self.ready.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the application is marked as ready.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::check::NamedChecker;
+use crate::metrics::CheckMetrics;
+use crate::status::{CheckCache, HealthResultEntry};
+
+use futures_channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// A handle to add and remove the checkers of a
+/// [`StatusWithChecks`](struct.StatusWithChecks.html) at runtime.
+///
+/// Created by
+/// [`StatusWithChecks::check_registry`](struct.StatusWithChecks.html#method.check_registry),
+/// it can be cloned and shared across threads.
+#[derive(Clone)]
+pub struct CheckRegistry {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    checkers: RwLock<Vec<Arc<NamedChecker>>>,
+    metrics: RwLock<CheckMetrics>,
+    cache: CheckCache,
+    subscribers: Mutex<Vec<UnboundedSender<Arc<NamedChecker>>>>,
+}
+
+impl fmt::Debug for CheckRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckRegistry")
+            .field("checkers", &self.names())
+            .finish()
+    }
+}
+
+impl CheckRegistry {
+    pub(crate) fn new(metrics: CheckMetrics) -> Self {
+        CheckRegistry {
+            inner: Arc::new(Inner {
+                checkers: RwLock::new(Vec::new()),
+                metrics: RwLock::new(metrics),
+                cache: CheckCache::default(),
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Adds a [`NamedChecker`](struct.NamedChecker.html), replacing any checker with the same
+    /// name along with its latest result and metric series.
+    ///
+    /// Checks run in the background start running on the next tick of the
+    /// [`CheckRunner`](struct.CheckRunner.html), until then the check is reported as pending.
+    pub fn register(&self, checker: NamedChecker) {
+        let checker = Arc::new(checker);
+
+        // The cache is locked before the checkers, in the same order as when recording a result
+        let mut cache = self
+            .inner
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut checkers = self.write();
+
+        if let Some(position) = checkers.iter().position(|c| c.name() == checker.name()) {
+            let replaced = checkers.remove(position);
+
+            cache.remove(replaced.name());
+            self.metrics().remove(&replaced, "");
+        }
+
+        checkers.push(checker.clone());
+
+        // Subscribers are notified while holding the lock, so that none can miss the checker
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|subscriber| subscriber.unbounded_send(checker.clone()).is_ok());
+    }
+
+    /// Removes the checker with the name, along with its latest result and metric series.
+    ///
+    /// Returns whether there was a checker with the name.
+    pub fn unregister(&self, name: &str) -> bool {
+        let removed = {
+            let mut checkers = self.write();
+            let position = checkers.iter().position(|c| c.name() == name);
+            position.map(|position| checkers.remove(position))
+        };
+
+        match removed {
+            Some(checker) => {
+                self.forget(&checker);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a checker with the name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.read().iter().any(|c| c.name() == name)
+    }
+
+    /// The names of the registered checkers.
+    pub fn names(&self) -> Vec<String> {
+        self.read().iter().map(|c| c.name().to_owned()).collect()
+    }
+
+    /// The registered checkers.
+    pub(crate) fn checkers(&self) -> Vec<Arc<NamedChecker>> {
+        self.read().clone()
+    }
+
+    /// Whether this very checker is still registered, rather than one with the same name.
+    pub(crate) fn is_registered(&self, checker: &Arc<NamedChecker>) -> bool {
+        self.read().iter().any(|c| Arc::ptr_eq(c, checker))
+    }
+
+    /// The registered checkers, and a receiver of the checkers registered from now on.
+    pub(crate) fn subscribe(
+        &self,
+    ) -> (Vec<Arc<NamedChecker>>, UnboundedReceiver<Arc<NamedChecker>>) {
+        let (sender, receiver) = mpsc::unbounded();
+
+        let checkers = self.read();
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+
+        (checkers.clone(), receiver)
+    }
+
+    pub(crate) fn metrics(&self) -> CheckMetrics {
+        self.inner
+            .metrics
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub(crate) fn set_metrics(&self, metrics: CheckMetrics) {
+        *self
+            .inner
+            .metrics
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = metrics;
+    }
+
+    pub(crate) fn cache(&self) -> &CheckCache {
+        &self.inner.cache
+    }
+
+    /// Stores the latest result of a checker run in the background, returning whether it is
+    /// still registered.
+    pub(crate) fn record(&self, checker: &Arc<NamedChecker>, entry: HealthResultEntry) -> bool {
+        let mut cache = self
+            .inner
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Checked while holding the cache, so that an unregister cannot leave the result behind
+        if !self.is_registered(checker) {
+            return false;
+        }
+
+        cache.insert(checker.name().to_owned(), entry);
+        true
+    }
+
+    /// Deletes the latest result and metric series of a checker that is no longer registered,
+    /// unless another checker has since been registered with its name.
+    pub(crate) fn forget(&self, checker: &NamedChecker) {
+        let mut cache = self
+            .inner
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if !self.contains(checker.name()) {
+            cache.remove(checker.name());
+            self.metrics().remove(checker, "");
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<Arc<NamedChecker>>> {
+        self.inner
+            .checkers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Vec<Arc<NamedChecker>>> {
+        self.inner
+            .checkers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::status::{Status, StatusWithChecks};
+    use crate::{NamedChecker, StatusBuilder};
+
+    use ops_core::{async_trait, CheckResponse, Checker};
+    use prometheus::Registry;
+
+    struct Slow(Duration, &'static str);
+
+    #[async_trait]
+    impl Checker for Slow {
+        async fn check(&self) -> CheckResponse {
+            tokio::time::sleep(self.0).await;
+            CheckResponse::unhealthy(self.1, "", "")
+        }
+    }
+
+    fn checker(name: &str, delay: u64, output: &'static str) -> NamedChecker {
+        NamedChecker::new(name, Box::new(Slow(Duration::from_millis(delay), output)))
+    }
+
+    fn status() -> (StatusWithChecks, Registry) {
+        let registry = Registry::new();
+        let status = StatusBuilder::healthchecks("app", "")
+            .prometheus_registry(registry.clone())
+            .unwrap();
+
+        (status, registry)
+    }
+
+    /// The number of series of the check metrics for checks with the name.
+    fn series(registry: &Registry, name: &str) -> usize {
+        registry
+            .gather()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .filter(|metric| {
+                metric.get_label().iter().any(|label| {
+                    label.get_name() == "healthcheck_name" && label.get_value() == name
+                })
+            })
+            .count()
+    }
+
+    #[tokio::test]
+    async fn unregister_removes_series() {
+        let (status, registry) = status();
+        let status = status.checker(checker("check", 0, "bad"));
+
+        status.check().await;
+        assert!(series(&registry, "check") > 0);
+
+        assert!(status.check_registry().unregister("check"));
+        assert_eq!(series(&registry, "check"), 0);
+        assert!(!status.check_registry().unregister("check"));
+    }
+
+    #[tokio::test]
+    async fn unregister_while_checking_removes_series() {
+        let (status, registry) = status();
+        let status = status.checker(checker("slow", 300, "bad"));
+        let checks = status.check_registry();
+
+        let unregister = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            checks.unregister("slow")
+        };
+        let (_, unregistered) = tokio::join!(status.check(), unregister);
+
+        assert!(unregistered);
+        assert_eq!(series(&registry, "slow"), 0);
+    }
+
+    #[tokio::test]
+    async fn unregister_while_running_a_check_removes_series() {
+        let (status, registry) = status();
+        let status = status.checker(checker("slow", 300, "bad"));
+        let checks = status.check_registry();
+
+        let unregister = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            checks.unregister("slow")
+        };
+        let (entry, unregistered) = tokio::join!(status.run_check("slow"), unregister);
+
+        assert!(entry.is_some());
+        assert!(unregistered);
+        assert_eq!(series(&registry, "slow"), 0);
+    }
+
+    #[tokio::test]
+    async fn register_replaces_the_cached_result() {
+        let (status, _) = status();
+        let (status, _runner) = status.checker(checker("check", 0, "old")).background();
+
+        status.run_check("check").await;
+        let report = status.check().await.unwrap();
+        assert_eq!(report.checks()[0].output(), "old");
+
+        status.check_registry().register(checker("check", 0, "new"));
+
+        let report = status.check().await.unwrap();
+        assert!(report.checks()[0].is_pending());
+    }
+
+    #[tokio::test]
+    async fn register_removes_the_series_of_the_replaced_checker() {
+        let (status, registry) = status();
+        let status = status.checker(NamedChecker::group(
+            "group",
+            vec![checker("member", 0, "bad")],
+        ));
+
+        status.check().await;
+        assert!(series(&registry, "member") > 0);
+
+        status.check_registry().register(checker("group", 0, "bad"));
+        assert_eq!(series(&registry, "member"), 0);
+    }
+}
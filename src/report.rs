@@ -0,0 +1,186 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ops_core::Health;
+use serde::{Deserialize, Serialize};
+
+/// The health of the application and its checks, as served on `/__/health`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HealthReport {
+    name: String,
+    description: String,
+    health: Health,
+    #[serde(default)]
+    checks: Vec<HealthReportEntry>,
+}
+
+impl HealthReport {
+    pub(crate) fn new(
+        name: &str,
+        description: &str,
+        health: Health,
+        checks: Vec<HealthReportEntry>,
+    ) -> Self {
+        HealthReport {
+            name: name.to_owned(),
+            description: description.to_owned(),
+            health,
+            checks,
+        }
+    }
+
+    /// The name of the application.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The description of the application.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The overall health of the application.
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    /// The results of the individual checks.
+    pub fn checks(&self) -> &[HealthReportEntry] {
+        &self.checks
+    }
+}
+
+/// The result of a single check in a [`HealthReport`](struct.HealthReport.html).
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HealthReportEntry {
+    name: String,
+    health: Health,
+    #[serde(default)]
+    output: String,
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    impact: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pending: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_seconds: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_run_timestamp: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staleness_seconds: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    checks: Vec<HealthReportEntry>,
+}
+
+impl HealthReportEntry {
+    pub(crate) fn new(
+        name: &str,
+        health: Health,
+        output: &str,
+        action: Option<&str>,
+        impact: Option<&str>,
+    ) -> Self {
+        HealthReportEntry {
+            name: name.to_owned(),
+            health,
+            output: output.to_owned(),
+            action: action.map(str::to_owned),
+            impact: impact.map(str::to_owned),
+            pending: false,
+            duration_seconds: None,
+            last_run_timestamp: None,
+            staleness_seconds: None,
+            checks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_pending(mut self, pending: bool) -> Self {
+        self.pending = pending;
+        self
+    }
+
+    pub(crate) fn with_duration(mut self, duration: Option<Duration>) -> Self {
+        self.duration_seconds = duration.map(|d| d.as_secs_f64());
+        self
+    }
+
+    pub(crate) fn with_last_run(mut self, last_run: Option<SystemTime>) -> Self {
+        self.last_run_timestamp = last_run.map(|last_run| {
+            last_run
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64()
+        });
+        self
+    }
+
+    pub(crate) fn with_staleness(mut self, staleness: Option<Duration>) -> Self {
+        self.staleness_seconds = staleness.map(|s| s.as_secs_f64());
+        self
+    }
+
+    pub(crate) fn with_checks(mut self, checks: Vec<HealthReportEntry>) -> Self {
+        self.checks = checks;
+        self
+    }
+
+    /// The name of the check.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The health of the check.
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    /// Text representation of the result of the check.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Action to resolve the issue if non-healthy.
+    pub fn action(&self) -> Option<&str> {
+        self.action.as_deref()
+    }
+
+    /// Impact of not fixing the issue.
+    pub fn impact(&self) -> Option<&str> {
+        self.impact.as_deref()
+    }
+
+    /// Whether the check is run in the background and has not completed yet.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// How long the check took to run.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_seconds.and_then(seconds)
+    }
+
+    /// When the check was last run in the background.
+    pub fn last_run(&self) -> Option<SystemTime> {
+        self.last_run_timestamp
+            .and_then(seconds)
+            .map(|since_epoch| UNIX_EPOCH + since_epoch)
+    }
+
+    /// How long ago the check was last run in the background.
+    pub fn staleness(&self) -> Option<Duration> {
+        self.staleness_seconds.and_then(seconds)
+    }
+
+    /// The results of the checks of a group, nested inside the result of the group.
+    pub fn checks(&self) -> &[HealthReportEntry] {
+        &self.checks
+    }
+}
+
+fn seconds(secs: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::check::NamedChecker;
+use crate::registry::CheckRegistry;
+use crate::status::run_checker;
+
+use futures_timer::Delay;
+use futures_util::future::{self, Either};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+
+/// Runs the checks of a [`StatusWithChecks`](struct.StatusWithChecks.html) in the background.
+///
+/// Created by [`StatusWithChecks::background`](struct.StatusWithChecks.html#method.background).
+pub struct CheckRunner {
+    registry: CheckRegistry,
+    timeout: Duration,
+    interval: Duration,
+}
+
+impl fmt::Debug for CheckRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckRunner")
+            .field("registry", &self.registry)
+            .field("timeout", &self.timeout)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+impl CheckRunner {
+    pub(crate) fn new(registry: CheckRegistry, timeout: Duration, interval: Duration) -> Self {
+        Self {
+            registry,
+            timeout,
+            interval,
+        }
+    }
+
+    /// Runs every check on its own interval, forever.
+    ///
+    /// Checkers added to the [`CheckRegistry`](struct.CheckRegistry.html) start running as they
+    /// are registered, and stop once unregistered.
+    ///
+    /// This should be spawned onto the runtime of the application.
+    pub async fn run(self) {
+        let (checkers, mut registered) = self.registry.subscribe();
+
+        let mut runs = checkers
+            .into_iter()
+            .map(|c| self.run_periodically(c))
+            .collect::<FuturesUnordered<_>>();
+
+        loop {
+            let checker = if runs.is_empty() {
+                registered.next().await
+            } else {
+                match future::select(registered.next(), runs.next()).await {
+                    Either::Left((checker, _)) => checker,
+                    // A check stopped running as it was unregistered
+                    Either::Right(_) => continue,
+                }
+            };
+
+            match checker {
+                Some(checker) => runs.push(self.run_periodically(checker)),
+                None => break,
+            }
+        }
+
+        // The registry is gone, so no more checkers can be registered or unregistered
+        while runs.next().await.is_some() {}
+    }
+
+    async fn run_periodically(&self, checker: Arc<NamedChecker>) {
+        let interval = checker.interval().unwrap_or(self.interval);
+
+        while self.registry.is_registered(&checker) {
+            let entry = run_checker(&checker, "", self.timeout, &self.registry.metrics())
+                .await
+                .with_last_run(SystemTime::now());
+
+            if !self.registry.record(&checker, entry) {
+                // Unregistered while running, which left its metric series behind
+                self.registry.forget(&checker);
+                return;
+            }
+
+            Delay::new(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::status::Status;
+    use crate::{NamedChecker, StatusBuilder};
+
+    use ops_core::{async_trait, CheckResponse, Checker};
+
+    /// Counts its runs.
+    struct Counter(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl Checker for Counter {
+        async fn check(&self) -> CheckResponse {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            CheckResponse::healthy("ok")
+        }
+    }
+
+    fn counter(name: &str) -> (NamedChecker, Arc<AtomicUsize>) {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let checker = NamedChecker::new(name, Box::new(Counter(runs.clone())));
+        (checker, runs)
+    }
+
+    #[tokio::test]
+    async fn checks_are_pending_until_run() {
+        let (checker, runs) = counter("check");
+        let (status, _runner) = StatusBuilder::healthchecks("app", "")
+            .checker(checker)
+            .background();
+
+        let report = status.check().await.unwrap();
+        let entry = &report.checks()[0];
+
+        assert!(entry.is_pending());
+        assert_eq!(serde_json::to_value(entry).unwrap()["pending"], true);
+        assert_eq!(entry.last_run(), None);
+        assert_eq!(entry.staleness(), None);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn serves_the_latest_results() {
+        let (checker, runs) = counter("check");
+        let (status, runner) = StatusBuilder::healthchecks("app", "")
+            .checker(checker)
+            .background();
+
+        tokio::spawn(runner.run());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let report = status.check().await.unwrap();
+        let entry = &report.checks()[0];
+
+        assert!(!entry.is_pending());
+        assert_eq!(entry.output(), "ok");
+        assert!(entry.last_run().is_some());
+        assert!(entry.staleness().is_some());
+
+        let json = serde_json::to_value(entry).unwrap();
+        assert!(json["last_run_timestamp"].is_f64());
+        assert!(json["staleness_seconds"].is_f64());
+        assert!(json.get("pending").is_none());
+
+        // Serving the results does not run the checks
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn checks_run_on_their_own_interval() {
+        let (fast, fast_runs) = counter("fast");
+        let (slow, slow_runs) = counter("slow");
+        let (_status, runner) = StatusBuilder::healthchecks("app", "")
+            .check_interval(Duration::from_secs(60))
+            .checker(fast.with_interval(Duration::from_millis(50)))
+            .checker(slow)
+            .background();
+
+        tokio::spawn(runner.run());
+        tokio::time::sleep(Duration::from_millis(230)).await;
+
+        assert!(fast_runs.load(Ordering::SeqCst) >= 4);
+        assert_eq!(slow_runs.load(Ordering::SeqCst), 1);
+    }
+}
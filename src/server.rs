@@ -1,50 +1,140 @@
+use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::compression;
 use crate::error::Error;
+use crate::exposition;
+use crate::health::group_param;
 use crate::status::Status;
 use crate::Result;
 
+use futures_timer::Delay;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
 
 /// Starts a server and serves the ops endpoints.
 pub async fn server<S: Status + 'static>(addr: SocketAddr, status: S) -> Result<()> {
+    server_with_shutdown(
+        addr,
+        status,
+        futures_util::future::pending(),
+        Duration::ZERO,
+    )
+    .await
+}
+
+/// Starts a server and serves the ops endpoints until the shutdown signal completes.
+///
+/// Once signalled, `/__/ready` responds with 503 for the drain period, so that traffic stops
+/// being routed to the application, before the server stops accepting connections and waits for
+/// in-flight requests to complete.
+pub async fn server_with_shutdown<S, F>(
+    addr: SocketAddr,
+    status: S,
+    signal: F,
+    drain: Duration,
+) -> Result<()>
+where
+    S: Status + 'static,
+    F: Future<Output = ()>,
+{
     let status: Arc<S> = Arc::new(status);
+    let draining = Arc::new(AtomicBool::new(false));
+
+    let service = {
+        let draining = draining.clone();
 
-    let service = make_service_fn(move |_| {
-        let status = status.clone();
+        make_service_fn(move |_| {
+            let status = status.clone();
+            let draining = draining.clone();
+
+            async {
+                Ok::<_, Error>(service_fn(move |req| {
+                    router(req, status.clone(), draining.clone())
+                }))
+            }
+        })
+    };
 
-        async { Ok::<_, Error>(service_fn(move |req| router(req, status.clone()))) }
-    });
+    let shutdown = async move {
+        signal.await;
+        draining.store(true, Ordering::SeqCst);
+        Delay::new(drain).await;
+    };
 
-    Server::bind(&addr).serve(service).await.map_err(Into::into)
+    Server::bind(&addr)
+        .serve(service)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(Into::into)
 }
 
-async fn router<S: Status + 'static>(req: Request<Body>, status: Arc<S>) -> Result<Response<Body>> {
+async fn router<S: Status + 'static>(
+    req: Request<Body>,
+    status: Arc<S>,
+    draining: Arc<AtomicBool>,
+) -> Result<Response<Body>> {
+    let accept_encoding = req
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|a| a.to_str().ok());
+
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/__/about") => about(status.clone()).await,
-        (&Method::GET, "/__/metrics") => metrics().await,
-        (&Method::GET, "/__/ready") => ready(status.clone()).await,
-        (&Method::GET, "/__/health") => health(status.clone()).await,
+        (&Method::GET, "/__/metrics") => {
+            let accept = req.headers().get(header::ACCEPT);
+            metrics(
+                status.clone(),
+                accept.and_then(|a| a.to_str().ok()),
+                accept_encoding,
+            )
+            .await
+        }
+        (&Method::GET, "/__/ready") => ready(status.clone(), &draining).await,
+        (&Method::GET, "/__/live") => probe(status.live().await, "live\n"),
+        (&Method::GET, "/__/startup") => probe(status.started().await, "started\n"),
+        (&Method::GET, "/__/health") => {
+            let group = group_param(req.uri().query());
+            health(status.clone(), group, accept_encoding).await
+        }
+        (&Method::GET, path) if path.starts_with("/__/health/") => {
+            run_check(status.clone(), &path["/__/health/".len()..]).await
+        }
         _ => Ok(Response::builder()
             .status(StatusCode::NOT_FOUND)
             .body(Body::from("not found"))?),
     }
 }
 
-async fn ready<S: Status + 'static>(status: Arc<S>) -> Result<Response<Body>> {
-    let resp = match status.ready().await {
+async fn ready<S: Status + 'static>(
+    status: Arc<S>,
+    draining: &AtomicBool,
+) -> Result<Response<Body>> {
+    let is_ready = if draining.load(Ordering::SeqCst) {
+        Some(false)
+    } else {
+        status.ready().await
+    };
+
+    probe(is_ready, "ready\n")
+}
+
+/// Responds to a probe, with 404 if the status does not support it.
+fn probe(state: Option<bool>, ok: &'static str) -> Result<Response<Body>> {
+    let resp = match state {
         None => Response::builder()
             .header(header::CONTENT_TYPE, "text/plain")
             .status(StatusCode::NOT_FOUND)
             .body(Body::from("not found"))?,
-        Some(is_ready) => {
-            if is_ready {
+        Some(is_ok) => {
+            if is_ok {
                 Response::builder()
                     .header(header::CONTENT_TYPE, "text/plain")
                     .status(StatusCode::OK)
-                    .body(Body::from("ready\n"))?
+                    .body(Body::from(ok))?
             } else {
                 Response::builder()
                     .header(header::CONTENT_TYPE, "text/plain")
@@ -56,12 +146,44 @@ async fn ready<S: Status + 'static>(status: Arc<S>) -> Result<Response<Body>> {
     Ok(resp)
 }
 
-async fn health<S: Status + 'static>(status: Arc<S>) -> Result<Response<Body>> {
-    let resp = match status.check().await {
+async fn health<S: Status + 'static>(
+    status: Arc<S>,
+    group: Option<&str>,
+    accept_encoding: Option<&str>,
+) -> Result<Response<Body>> {
+    let report = match group {
+        Some(group) => status.check_group(group).await,
+        None => status.check().await,
+    };
+
+    let resp = match report {
+        None if group.is_some() => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("No such group"))?,
         None => Response::builder()
             .status(StatusCode::NOT_FOUND)
             .body(Body::from("No health checks"))?,
-        Some(resp) => match serde_json::to_string(&resp.to_json()) {
+        Some(resp) => match serde_json::to_vec(&resp) {
+            Ok(payload) => compressed(
+                status.as_ref(),
+                StatusCode::from_u16(status.health_status_code(resp.health()))
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                accept_encoding,
+                "application/json",
+                payload,
+            )?,
+            Err(err) => err_response(err)?,
+        },
+    };
+    Ok(resp)
+}
+
+async fn run_check<S: Status + 'static>(status: Arc<S>, name: &str) -> Result<Response<Body>> {
+    let resp = match status.run_check(name).await {
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("No such check"))?,
+        Some(entry) => match serde_json::to_string(&entry) {
             Ok(payload) => Response::builder()
                 .status(StatusCode::OK)
                 .header(header::CONTENT_TYPE, "application/json")
@@ -72,23 +194,50 @@ async fn health<S: Status + 'static>(status: Arc<S>) -> Result<Response<Body>> {
     Ok(resp)
 }
 
-async fn metrics() -> Result<Response<Body>> {
-    let resp = match render_metrics() {
-        Ok(rendered_metrics) => match String::from_utf8(rendered_metrics) {
-            Ok(rendered_metrics) => Response::builder()
-                .status(StatusCode::OK)
-                .header(
-                    header::CONTENT_TYPE,
-                    "text/plain; version=0.0.4; charset=utf-8",
-                )
-                .body(Body::from(rendered_metrics))?,
-            Err(err) => err_response(err)?,
-        },
+async fn metrics<S: Status + 'static>(
+    status: Arc<S>,
+    accept: Option<&str>,
+    accept_encoding: Option<&str>,
+) -> Result<Response<Body>> {
+    let resp = match exposition::render(&status.gather(), accept) {
+        Ok(rendered) => compressed(
+            status.as_ref(),
+            StatusCode::OK,
+            accept_encoding,
+            rendered.content_type,
+            rendered.body,
+        )?,
         Err(err) => err_response(err)?,
     };
     Ok(resp)
 }
 
+/// Responds with the body, compressed if the client accepts it and it is large enough.
+fn compressed<S: Status>(
+    status: &S,
+    status_code: StatusCode,
+    accept_encoding: Option<&str>,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<Response<Body>> {
+    let compressed =
+        match compression::compress(body, accept_encoding, status.compression_min_size()) {
+            Ok(compressed) => compressed,
+            Err(err) => return err_response(err),
+        };
+
+    let mut resp = Response::builder()
+        .status(status_code)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::VARY, "Accept-Encoding");
+
+    if let Some(encoding) = compressed.encoding.content_encoding() {
+        resp = resp.header(header::CONTENT_ENCODING, encoding);
+    }
+
+    Ok(resp.body(Body::from(compressed.body))?)
+}
+
 async fn about<S: Status + 'static>(status: Arc<S>) -> Result<Response<Body>> {
     let resp = match serde_json::to_string(&status.about()) {
         Ok(payload) => Response::builder()
@@ -107,15 +256,3 @@ fn err_response<I: Into<Error>>(err: I) -> Result<Response<Body>> {
         .body(Body::from(err.into().to_string()))?;
     Ok(resp)
 }
-
-fn render_metrics() -> Result<Vec<u8>> {
-    use prometheus::{gather, Encoder, TextEncoder};
-
-    let metric_family = gather();
-
-    let mut writer = Vec::<u8>::new();
-    let encoder = TextEncoder::new();
-    encoder.encode(&metric_family, &mut writer)?;
-
-    Ok(writer)
-}
@@ -1,116 +1,162 @@
+use std::any::Any;
+use std::collections::HashMap;
 use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::about::{About, Link, Owner};
+use crate::aggregation::{Aggregation, CheckHealth};
+use crate::build_info::BuildInfo;
+use crate::check::{Group, NamedChecker};
+use crate::compression::DEFAULT_COMPRESSION_MIN_SIZE;
+use crate::health::HealthStatusCodes;
+use crate::liveness::Heartbeat;
+use crate::metrics::{self, CheckMetrics};
+use crate::readiness::ReadinessHandle;
+use crate::registry::CheckRegistry;
+use crate::report::{HealthReport, HealthReportEntry};
+use crate::runner::CheckRunner;
+use crate::Result;
+
+use futures_timer::Delay;
+use futures_util::future::{self, BoxFuture, Either, FutureExt};
+use ops_core::{async_trait, CheckResponse, Checker, Health};
+use prometheus::proto::MetricFamily;
+use prometheus::Registry;
 
-use crate::check::NamedChecker;
+/// The timeout applied to checkers that do not set their own.
+const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
 
-use once_cell::sync::Lazy;
-use ops_core::{async_trait, CheckResponse, Checker, Health};
-use prometheus::{opts, register_gauge_vec, GaugeVec};
-use serde_json::{json, Value};
-
-const HEALTHCHECK_NAME: &str = "healthcheck_name";
-const HEALTHCHECK_RESULT: &str = "healthcheck_result";
-const HEALTHCHECK_STATUS: &str = "healthcheck_status";
-
-static CHECK_RESULT_GAUGE: Lazy<GaugeVec> = Lazy::new(|| {
-    register_gauge_vec!(
-        opts!(
-            HEALTHCHECK_STATUS,
-            "Meters the healthcheck status based for each check and for each result"
-        ),
-        &[HEALTHCHECK_NAME, HEALTHCHECK_RESULT]
-    )
-    .unwrap()
-});
+/// The interval applied to checkers run in the background that do not set their own.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
-enum Ready {
-    Always,
-    Never,
-}
+/// The latest results of checks run in the background, keyed by checker name.
+pub(crate) type CheckCache = Arc<Mutex<HashMap<String, HealthResultEntry>>>;
 
 #[async_trait]
 pub trait Status: Send + Sync {
-    /// Details of the application, as JSON.
-    fn about(&self) -> Value;
+    /// Details of the application.
+    fn about(&self) -> About;
 
     /// Determines the readiness of the application.
     async fn ready(&self) -> Option<bool>;
 
+    /// Determines the liveness of the application.
+    async fn live(&self) -> Option<bool>;
+
+    /// Determines whether the application has started.
+    async fn started(&self) -> Option<bool>;
+
     /// Checks the health of the application.
-    async fn check(&self) -> Option<HealthResult>;
-}
+    async fn check(&self) -> Option<HealthReport>;
 
-#[derive(Debug)]
-/// Converts the health result entry to JSON.
-pub struct HealthResult {
-    name: String,
-    description: String,
-    health: Health,
-    checks: Vec<HealthResultEntry>,
-}
+    /// Checks the health of a group of checks, given its path such as `storage.replicas`.
+    async fn check_group(&self, group: &str) -> Option<HealthReport>;
 
-impl HealthResult {
-    fn new(
-        name: String,
-        description: String,
-        health: Health,
-        checks: Vec<HealthResultEntry>,
-    ) -> HealthResult {
-        HealthResult {
-            name,
-            description,
-            health,
-            checks,
-        }
-    }
+    /// Runs a single check, given its name or its path within groups such as `storage.db`.
+    async fn run_check(&self, name: &str) -> Option<HealthReportEntry>;
 
-    pub(crate) fn to_json(&self) -> Value {
-        let health: &'static str = self.health.into();
+    /// Gathers the metrics of the application.
+    fn gather(&self) -> Vec<MetricFamily>;
 
-        json!({
-            "name": self.name,
-            "description": self.description,
-            "health": health,
-            "checks": self.checks.iter().map(|c| c.to_json()).collect::<Vec<_>>(),
-        })
-    }
+    /// The smallest response body, in bytes, that is compressed.
+    fn compression_min_size(&self) -> usize;
+
+    /// The status code of a health response, for the health it reports.
+    fn health_status_code(&self, health: Health) -> u16;
 }
 
-#[derive(Debug)]
-struct HealthResultEntry {
+#[derive(Clone, Debug)]
+pub(crate) struct HealthResultEntry {
     name: String,
     health: Health,
     output: String,
     action: Option<String>,
     impact: Option<String>,
+    critical: bool,
+    weight: f64,
+    pending: bool,
+    duration: Option<Duration>,
+    last_run: Option<SystemTime>,
+    staleness: Option<Duration>,
+    group: bool,
+    checks: Vec<HealthResultEntry>,
 }
 
 impl HealthResultEntry {
     fn new(
-        name: String,
+        checker: &NamedChecker,
         health: Health,
         output: String,
         action: Option<String>,
         impact: Option<String>,
     ) -> HealthResultEntry {
         HealthResultEntry {
-            name,
+            name: checker.name().to_owned(),
             health,
             output,
             action,
             impact,
+            critical: checker.is_critical(),
+            weight: checker.weight(),
+            pending: false,
+            duration: None,
+            last_run: None,
+            staleness: None,
+            group: checker.checkers().is_some(),
+            checks: Vec::new(),
         }
     }
 
-    fn to_json(&self) -> Value {
-        let health: &'static str = self.health.into();
+    /// An entry for a check run in the background that has not completed yet.
+    fn pending(checker: &NamedChecker) -> HealthResultEntry {
+        let checks = checker
+            .checkers()
+            .unwrap_or_default()
+            .iter()
+            .map(HealthResultEntry::pending)
+            .collect();
 
-        json!({
-            "name": self.name,
-            "health": health,
-            "output": self.output,
-            "action": self.action,
-            "impact": self.impact,
-        })
+        HealthResultEntry {
+            pending: true,
+            checks,
+            ..HealthResultEntry::new(
+                checker,
+                Health::Unhealthy,
+                "check is pending, it has not completed yet".to_owned(),
+                None,
+                None,
+            )
+        }
+    }
+
+    pub(crate) fn with_last_run(mut self, last_run: SystemTime) -> Self {
+        self.last_run = Some(last_run);
+        self
+    }
+
+    fn with_staleness(mut self, now: SystemTime) -> Self {
+        self.staleness = self
+            .last_run
+            .map(|last_run| now.duration_since(last_run).unwrap_or_default());
+        self
+    }
+
+    fn report(&self) -> HealthReportEntry {
+        HealthReportEntry::new(
+            &self.name,
+            self.health,
+            &self.output,
+            self.action.as_deref(),
+            self.impact.as_deref(),
+        )
+        .with_pending(self.pending)
+        .with_duration(self.duration)
+        .with_last_run(self.last_run)
+        .with_staleness(self.staleness)
+        .with_checks(self.checks.iter().map(HealthResultEntry::report).collect())
     }
 }
 
@@ -119,291 +165,788 @@ impl HealthResultEntry {
 pub struct StatusBuilder {}
 
 impl StatusBuilder {
-    /// Always returns a status that is always ready.
+    /// Always returns a status that is ready, until marked otherwise by its
+    /// [`ReadinessHandle`](struct.ReadinessHandle.html).
     pub fn always(name: &str, description: &str) -> StatusNoChecks {
         StatusNoChecks {
-            name: name.to_owned(),
-            description: description.to_owned(),
-            ready: Some(Ready::Always),
-            revision: None,
-            owners: Vec::new(),
-            links: Vec::new(),
+            about: About::new(name, description),
+            ready: Some(ReadinessHandle::new(true)),
+            heartbeat: None,
+            registries: Vec::new(),
+            compression_min_size: DEFAULT_COMPRESSION_MIN_SIZE,
         }
     }
 
-    /// Never returns a status that is never ready.
+    /// Never returns a status that is not ready, until marked otherwise by its
+    /// [`ReadinessHandle`](struct.ReadinessHandle.html).
     pub fn never(name: &str, description: &str) -> StatusNoChecks {
         StatusNoChecks {
-            name: name.to_owned(),
-            description: description.to_owned(),
-            ready: Some(Ready::Never),
-            revision: None,
-            owners: Vec::new(),
-            links: Vec::new(),
+            about: About::new(name, description),
+            ready: Some(ReadinessHandle::new(false)),
+            heartbeat: None,
+            registries: Vec::new(),
+            compression_min_size: DEFAULT_COMPRESSION_MIN_SIZE,
         }
     }
 
     /// None returns a status has no concept of readiness.
     pub fn none(name: &str, description: &str) -> StatusNoChecks {
         StatusNoChecks {
-            name: name.to_owned(),
-            description: description.to_owned(),
+            about: About::new(name, description),
             ready: None,
-            revision: None,
-            owners: Vec::new(),
-            links: Vec::new(),
+            heartbeat: None,
+            registries: Vec::new(),
+            compression_min_size: DEFAULT_COMPRESSION_MIN_SIZE,
         }
     }
 
     /// Healthchecks returns a status that expects one or more [`NamedChecker`](struct.NamedChecker.html).
+    ///
+    /// The check metrics are registered into the default registry, and are not exported if that
+    /// fails as metrics with the same names are already registered, which is only logged. Use
+    /// [`prometheus_registry`](struct.StatusWithChecks.html#method.prometheus_registry) to get
+    /// the error instead.
     pub fn healthchecks(name: &str, description: &str) -> StatusWithChecks {
         StatusWithChecks {
-            name: name.to_owned(),
-            description: description.to_owned(),
-            checkers: Vec::new(),
-            revision: None,
-            owners: Vec::new(),
-            links: Vec::new(),
+            about: About::new(name, description),
+            checks: CheckRegistry::new(CheckMetrics::default()),
+            timeout: DEFAULT_CHECK_TIMEOUT,
+            interval: DEFAULT_CHECK_INTERVAL,
+            background: false,
+            aggregation: Aggregation::default(),
+            ready: ReadinessHandle::new(true),
+            heartbeat: None,
+            startup: Startup::default(),
+            registries: Vec::new(),
+            compression_min_size: DEFAULT_COMPRESSION_MIN_SIZE,
+            status_codes: None,
         }
     }
 }
 
 /// A status with no health checks
 pub struct StatusNoChecks {
-    name: String,
-    description: String,
-    ready: Option<Ready>,
-    revision: Option<String>,
-    owners: Vec<Owner>,
-    links: Vec<Link>,
+    about: About,
+    ready: Option<ReadinessHandle>,
+    heartbeat: Option<Heartbeat>,
+    registries: Vec<Registry>,
+    compression_min_size: usize,
 }
 
 impl fmt::Debug for StatusNoChecks {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("StatusNoChecks")
-            .field("name", &self.name)
-            .field("description", &self.description)
+            .field("name", &self.about.name())
             .finish()
     }
 }
 
 impl StatusNoChecks {
+    /// Returns a handle to mark the status as ready or not ready at runtime.
+    ///
+    /// Statuses with no concept of readiness, built with
+    /// [`StatusBuilder::none`](struct.StatusBuilder.html#method.none), have no handle.
+    pub fn readiness_handle(&self) -> Option<ReadinessHandle> {
+        self.ready.clone()
+    }
+
+    /// Serves `/__/live`, which fails once the [`Heartbeat`](struct.Heartbeat.html) stops.
+    pub fn liveness(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    /// Adds a registry to gather metrics from, instead of the default registry.
+    pub fn prometheus_registry(mut self, registry: Registry) -> Self {
+        self.registries.push(registry);
+        self
+    }
+
+    /// Sets the smallest response body, in bytes, that is compressed when the client accepts
+    /// it, defaults to 1024.
+    ///
+    /// Compression requires the `gzip` or `zstd` feature.
+    pub fn compression_min_size(mut self, min_size: usize) -> Self {
+        self.compression_min_size = min_size;
+        self
+    }
+
     /// Sets the revision, this should be a version control ref.
     pub fn revision(mut self, revision: &str) -> Self {
-        self.revision = Some(revision.to_owned());
+        self.about = self.about.with_revision(revision);
+        self
+    }
+
+    /// Sets the version of the application.
+    pub fn version(mut self, version: &str) -> Self {
+        self.about = self.about.with_version(version);
+        self
+    }
+
+    /// Sets the details of the build, such as those built by
+    /// [`build_info!`](macro.build_info.html), keeping any that are not set in it.
+    pub fn build_info(mut self, build_info: BuildInfo) -> Self {
+        self.about = self.about.with_build_info(build_info);
         self
     }
 
-    /// Adds an owner.
+    /// Adds an owner that can be reached on a Slack channel.
     pub fn owner(mut self, name: &str, slack: &str) -> Self {
-        self.owners.push(Owner::new(name, slack));
+        self.about = self.about.with_owner(Owner::new(name).with_slack(slack));
+        self
+    }
+
+    /// Adds an [`Owner`](struct.Owner.html), with any of its contact details.
+    pub fn owner_contact(mut self, owner: Owner) -> Self {
+        self.about = self.about.with_owner(owner);
         self
     }
 
     /// Adds a link.
     pub fn link(mut self, description: &str, url: &str) -> Self {
-        self.links.push(Link::new(description, url));
+        self.about = self.about.with_link(Link::new(description, url));
         self
     }
 }
 
 #[async_trait]
 impl Status for StatusNoChecks {
-    fn about(&self) -> Value {
-        json!({
-            "name": self.name,
-            "description": self.description,
-            "links": self.links.iter().map(|l| l.to_json()).collect::<Vec<_>>(),
-            "owners": self.owners.iter().map(|o| o.to_json()).collect::<Vec<_>>(),
-            "build-info": {
-                "revision": self.revision,
-            },
-        })
+    fn about(&self) -> About {
+        self.about.clone()
     }
 
     async fn ready(&self) -> Option<bool> {
-        match self.ready {
-            Some(Ready::Always) => Some(true),
-            Some(Ready::Never) => Some(false),
-            None => None,
-        }
+        self.ready.as_ref().map(ReadinessHandle::is_ready)
+    }
+
+    async fn live(&self) -> Option<bool> {
+        self.heartbeat.as_ref().map(Heartbeat::is_alive)
+    }
+
+    async fn started(&self) -> Option<bool> {
+        None
+    }
+
+    async fn check(&self) -> Option<HealthReport> {
+        None
+    }
+
+    async fn check_group(&self, _group: &str) -> Option<HealthReport> {
+        None
     }
 
-    async fn check(&self) -> Option<HealthResult> {
+    async fn run_check(&self, _name: &str) -> Option<HealthReportEntry> {
         None
     }
+
+    fn gather(&self) -> Vec<MetricFamily> {
+        metrics::gather(&self.registries)
+    }
+
+    fn compression_min_size(&self) -> usize {
+        self.compression_min_size
+    }
+
+    fn health_status_code(&self, _health: Health) -> u16 {
+        200
+    }
 }
 
 /// A status with health checks
 pub struct StatusWithChecks {
-    name: String,
-    description: String,
-    checkers: Vec<NamedChecker>,
-    revision: Option<String>,
-    owners: Vec<Owner>,
-    links: Vec<Link>,
+    about: About,
+    checks: CheckRegistry,
+    timeout: Duration,
+    interval: Duration,
+    background: bool,
+    aggregation: Aggregation,
+    ready: ReadinessHandle,
+    heartbeat: Option<Heartbeat>,
+    startup: Startup,
+    registries: Vec<Registry>,
+    compression_min_size: usize,
+    status_codes: Option<HealthStatusCodes>,
 }
 
 impl fmt::Debug for StatusWithChecks {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("StatusWithChecks")
-            .field("name", &self.name)
-            .field("description", &self.description)
+            .field("name", &self.about.name())
+            .field("timeout", &self.timeout)
+            .field("interval", &self.interval)
+            .field("background", &self.background)
+            .field("aggregation", &self.aggregation)
+            .field("status_codes", &self.status_codes)
             .finish()
     }
 }
 
 impl StatusWithChecks {
     /// Adds a [`NamedChecker`](`struct.NamedChecker.html`).
-    pub fn checker(mut self, checker: NamedChecker) -> Self {
-        self.checkers.push(checker);
+    ///
+    /// Checker names are unique, so a checker replaces any checker added before with the same
+    /// name.
+    pub fn checker(self, checker: NamedChecker) -> Self {
+        self.checks.register(checker);
+        self
+    }
+
+    /// Returns a handle to add and remove checkers at runtime.
+    pub fn check_registry(&self) -> CheckRegistry {
+        self.checks.clone()
+    }
+
+    /// Sets the default timeout for checkers, defaults to 10 seconds.
+    ///
+    /// A checker that does not complete in time is reported as unhealthy.
+    pub fn check_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the default interval for checkers run in the background, defaults to 30 seconds.
+    pub fn check_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets how the health of the checks is combined into the overall health, defaults to
+    /// [`Aggregation::WorstOf`](enum.Aggregation.html#variant.WorstOf).
+    ///
+    /// Readiness checks are combined the same way, and the application is ready unless they are
+    /// unhealthy overall.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Returns a handle to mark the status as ready or not ready at runtime.
+    ///
+    /// The status is only ready when marked as ready and its readiness checks pass.
+    pub fn readiness_handle(&self) -> ReadinessHandle {
+        self.ready.clone()
+    }
+
+    /// Serves `/__/live`, which fails once the [`Heartbeat`](struct.Heartbeat.html) stops.
+    pub fn liveness(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    /// Adds a [`NamedChecker`](struct.NamedChecker.html) that must pass once for the application
+    /// to have started.
+    ///
+    /// With startup checkers, `/__/startup` is served and fails until every startup checker has
+    /// passed, after which it always succeeds and the startup checkers are not run again.
+    pub fn startup_checker(mut self, checker: NamedChecker) -> Self {
+        self.startup
+            .checkers
+            .push((checker, AtomicBool::new(false)));
+        self
+    }
+
+    /// Adds a registry to gather metrics from, instead of the default registry.
+    ///
+    /// The check metrics are registered into the first registry added.
+    pub fn prometheus_registry(mut self, registry: Registry) -> Result<Self> {
+        if self.registries.is_empty() {
+            self.checks
+                .set_metrics(CheckMetrics::registered(&registry)?);
+        }
+
+        self.registries.push(registry);
+        Ok(self)
+    }
+
+    /// Switches the status to serve the latest results of checks run in the background.
+    ///
+    /// The returned [`CheckRunner`](struct.CheckRunner.html) must be spawned for the checks
+    /// to run, until then every check is reported as pending.
+    pub fn background(mut self) -> (Self, CheckRunner) {
+        let runner = CheckRunner::new(self.checks.clone(), self.timeout, self.interval);
+
+        self.background = true;
+
+        (self, runner)
+    }
+
+    /// Sets the smallest response body, in bytes, that is compressed when the client accepts
+    /// it, defaults to 1024.
+    ///
+    /// Compression requires the `gzip` or `zstd` feature.
+    pub fn compression_min_size(mut self, min_size: usize) -> Self {
+        self.compression_min_size = min_size;
+        self
+    }
+
+    /// Responds on `/__/health` with a status code derived from the overall health, such as
+    /// 503 when unhealthy with the [default](struct.HealthStatusCodes.html#impl-Default) codes.
+    ///
+    /// Without it, every health response is 200 whatever the health. The body is the same
+    /// either way.
+    pub fn health_status_codes(mut self, status_codes: HealthStatusCodes) -> Self {
+        self.status_codes = Some(status_codes);
         self
     }
 
     /// Sets the revision, this should be a version control ref.
     pub fn revision(mut self, revision: &str) -> Self {
-        self.revision = Some(revision.to_owned());
+        self.about = self.about.with_revision(revision);
         self
     }
 
-    /// Adds an owner.
+    /// Sets the version of the application.
+    pub fn version(mut self, version: &str) -> Self {
+        self.about = self.about.with_version(version);
+        self
+    }
+
+    /// Sets the details of the build, such as those built by
+    /// [`build_info!`](macro.build_info.html), keeping any that are not set in it.
+    pub fn build_info(mut self, build_info: BuildInfo) -> Self {
+        self.about = self.about.with_build_info(build_info);
+        self
+    }
+
+    /// Adds an owner that can be reached on a Slack channel.
     pub fn owner(mut self, name: &str, slack: &str) -> Self {
-        self.owners.push(Owner::new(name, slack));
+        self.about = self.about.with_owner(Owner::new(name).with_slack(slack));
+        self
+    }
+
+    /// Adds an [`Owner`](struct.Owner.html), with any of its contact details.
+    pub fn owner_contact(mut self, owner: Owner) -> Self {
+        self.about = self.about.with_owner(owner);
         self
     }
 
     /// Adds a link.
     pub fn link(mut self, description: &str, url: &str) -> Self {
-        self.links.push(Link::new(description, url));
+        self.about = self.about.with_link(Link::new(description, url));
         self
     }
 
-    async fn use_health_check(&self) -> bool {
-        match self.check().await.unwrap().health {
+    async fn use_readiness_checks(&self) -> bool {
+        let checks = self.run_checks(NamedChecker::affects_readiness).await;
+
+        match self.aggregate(&checks) {
             Health::Healthy => true,
             Health::Degraded => true,
             Health::Unhealthy => false,
         }
     }
 
-    fn update_check_metrics(&self, checker: &NamedChecker, response: &CheckResponse) {
-        use std::collections::HashMap;
+    /// Aggregates the results of the checks, capping non-critical checks at degraded.
+    fn aggregate(&self, checks: &[HealthResultEntry]) -> Health {
+        self.aggregation.aggregate(
+            &checks
+                .iter()
+                .map(|c| CheckHealth::new(&c.name, c.health, c.critical, c.weight))
+                .collect::<Vec<_>>(),
+        )
+    }
 
-        let res = response.health();
+    /// Runs the checkers matching the filter, or reads their latest results in the background.
+    async fn run_checks(&self, filter: impl Fn(&NamedChecker) -> bool) -> Vec<HealthResultEntry> {
+        let checkers = self.checks.checkers();
+        let checkers = checkers.iter().filter(|c| filter(c));
 
-        let map = [
-            (HEALTHCHECK_NAME, checker.name()),
-            (HEALTHCHECK_RESULT, res.into()),
-        ]
-        .iter()
-        .cloned()
-        .collect::<HashMap<&str, &str>>();
-
-        crate::health::HEALTH_STATUSES.iter().for_each(|hs| {
-            if &response.health() == hs {
-                CHECK_RESULT_GAUGE.with(&map).set(1.0);
-            } else {
-                CHECK_RESULT_GAUGE.with(&map).set(0.0);
+        if self.background {
+            Self::cached_checks(checkers, self.checks.cache())
+        } else {
+            let checkers = checkers.collect::<Vec<_>>();
+            let metrics = self.checks.metrics();
+
+            let checks = future::join_all(
+                checkers
+                    .iter()
+                    .map(|c| run_checker(c, "", self.timeout, &metrics)),
+            )
+            .await;
+
+            self.forget_unregistered(&checkers);
+
+            checks
+        }
+    }
+
+    /// Deletes the metric series of checkers unregistered while they were running, as running
+    /// them exported the series again.
+    fn forget_unregistered(&self, checkers: &[&Arc<NamedChecker>]) {
+        for checker in checkers {
+            if !self.checks.is_registered(checker) {
+                self.checks.forget(checker);
             }
-        });
+        }
+    }
+
+    fn cached_checks<'a>(
+        checkers: impl Iterator<Item = &'a Arc<NamedChecker>>,
+        cache: &CheckCache,
+    ) -> Vec<HealthResultEntry> {
+        let cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = SystemTime::now();
+
+        checkers
+            .map(|checker| match cache.get(checker.name()) {
+                Some(entry) => entry.clone().with_staleness(now),
+                None => HealthResultEntry::pending(checker),
+            })
+            .collect()
     }
 }
 
 #[async_trait]
 impl Status for StatusWithChecks {
-    fn about(&self) -> Value {
-        json!({
-            "name": self.name,
-            "description": self.description,
-            "links": self.links.iter().map(|l| l.to_json()).collect::<Vec<_>>(),
-            "owners": self.owners.iter().map(|o| o.to_json()).collect::<Vec<_>>(),
-            "build-info": {
-                "revision": self.revision,
-            },
-        })
+    fn about(&self) -> About {
+        self.about.clone()
     }
 
     async fn ready(&self) -> Option<bool> {
-        Some(self.use_health_check().await)
+        Some(self.ready.is_ready() && self.use_readiness_checks().await)
     }
 
-    async fn check(&self) -> Option<HealthResult> {
-        let checkers = self.checkers.iter().map(|c| c.check());
+    async fn live(&self) -> Option<bool> {
+        self.heartbeat.as_ref().map(Heartbeat::is_alive)
+    }
 
-        let checks = futures_util::future::join_all(checkers).await;
+    async fn started(&self) -> Option<bool> {
+        if self.startup.checkers.is_empty() {
+            None
+        } else {
+            Some(self.startup.run(self.timeout, &self.checks.metrics()).await)
+        }
+    }
 
-        let checks = checks.iter().zip(self.checkers.iter());
+    async fn check(&self) -> Option<HealthReport> {
+        let checks = self.run_checks(NamedChecker::affects_health).await;
 
-        let mut health_result = HealthResult::new(
-            self.name.to_owned(),
-            self.description.to_owned(),
-            Health::Unhealthy,
-            checks
-                .map(|(resp, checker)| {
-                    self.update_check_metrics(checker, resp);
-                    HealthResultEntry::new(
-                        checker.name().to_owned(),
-                        resp.health().to_owned(),
-                        resp.output().to_owned(),
-                        resp.action().map(str::to_string),
-                        resp.impact().map(str::to_string),
-                    )
-                })
-                .collect(),
-        );
-
-        // Finds the highest enum value in the list of checker responses
-        health_result.health = match health_result
+        let health = self.aggregate(&checks);
+
+        Some(HealthReport::new(
+            self.about.name(),
+            self.about.description(),
+            health,
+            checks.iter().map(HealthResultEntry::report).collect(),
+        ))
+    }
+
+    async fn check_group(&self, group: &str) -> Option<HealthReport> {
+        let mut path = group.split('.');
+        let top = path.next()?;
+
+        // Only the top level group is run, and the report descends into its results
+        let mut entry = self
+            .run_checks(|c| c.affects_health() && c.name() == top && c.checkers().is_some())
+            .await
+            .into_iter()
+            .next()?;
+
+        for name in path {
+            entry = entry
+                .checks
+                .into_iter()
+                .find(|c| c.name == name && c.group)?;
+        }
+
+        Some(HealthReport::new(
+            self.about.name(),
+            self.about.description(),
+            entry.health,
+            entry.checks.iter().map(HealthResultEntry::report).collect(),
+        ))
+    }
+
+    async fn run_check(&self, name: &str) -> Option<HealthReportEntry> {
+        let mut path = name.split('.');
+        let top = path.next()?;
+
+        let registered = self
             .checks
-            .iter()
-            .max_by(|x, y| x.health.cmp(&y.health))
-        {
-            Some(status) => status.health,
-            None => Health::Unhealthy,
+            .checkers()
+            .into_iter()
+            .find(|c| c.name() == top)?;
+
+        let mut checker = registered.as_ref();
+        let mut group = String::new();
+
+        for name in path {
+            group = metrics::group_path(&group, checker.name());
+            checker = checker.checkers()?.iter().find(|c| c.name() == name)?;
+        }
+
+        let entry = run_checker(checker, &group, self.timeout, &self.checks.metrics()).await;
+
+        // A check run in the background is brought up to date, as if it had just run
+        let entry = if self.background && group.is_empty() {
+            let entry = entry.with_last_run(SystemTime::now());
+            self.checks.record(&registered, entry.clone());
+            entry
+        } else {
+            entry
         };
 
-        Some(health_result)
+        self.forget_unregistered(&[&registered]);
+
+        Some(entry.report())
+    }
+
+    fn gather(&self) -> Vec<MetricFamily> {
+        metrics::gather(&self.registries)
+    }
+
+    fn compression_min_size(&self) -> usize {
+        self.compression_min_size
+    }
+
+    fn health_status_code(&self, health: Health) -> u16 {
+        self.status_codes.map_or(200, |codes| codes.code(health))
     }
 }
 
-struct Owner {
-    name: String,
-    slack: String,
+/// One-shot checkers that must each pass once for the application to have started.
+#[derive(Debug, Default)]
+struct Startup {
+    checkers: Vec<(NamedChecker, AtomicBool)>,
+    started: AtomicBool,
 }
 
-impl Owner {
-    fn new(name: &str, slack: &str) -> Self {
-        Self {
-            name: name.to_owned(),
-            slack: slack.to_owned(),
+impl Startup {
+    /// Runs the startup checkers that have not passed yet, returning whether all have passed.
+    async fn run(&self, default_timeout: Duration, metrics: &CheckMetrics) -> bool {
+        if self.started.load(Ordering::SeqCst) {
+            return true;
         }
+
+        let pending = self
+            .checkers
+            .iter()
+            .filter(|(_, passed)| !passed.load(Ordering::SeqCst))
+            .map(|(checker, passed)| async move {
+                let entry = run_checker(checker, "", default_timeout, metrics).await;
+
+                if entry.health != Health::Unhealthy {
+                    passed.store(true, Ordering::SeqCst);
+                }
+            });
+
+        future::join_all(pending).await;
+
+        let started = self
+            .checkers
+            .iter()
+            .all(|(_, passed)| passed.load(Ordering::SeqCst));
+
+        if started {
+            self.started.store(true, Ordering::SeqCst);
+        }
+
+        started
     }
+}
+
+/// Runs a single checker, bounded by its timeout, and updates its metrics.
+///
+/// `group` is the path of the group the checker belongs to, which is empty at the top level.
+pub(crate) fn run_checker<'a>(
+    checker: &'a NamedChecker,
+    group: &'a str,
+    default_timeout: Duration,
+    metrics: &'a CheckMetrics,
+) -> BoxFuture<'a, HealthResultEntry> {
+    async move {
+        let start = Instant::now();
+
+        let (resp, checks) = match checker.as_group() {
+            Some(checkers) => {
+                let timeout = checker.timeout();
+                run_group(checkers, timeout, group, default_timeout, metrics).await
+            }
+            None => (
+                run_single(checker, group, default_timeout, metrics).await,
+                Vec::new(),
+            ),
+        };
+
+        let duration = start.elapsed();
+
+        metrics.update(checker, group, &resp, duration);
+
+        HealthResultEntry {
+            duration: Some(duration),
+            checks,
+            ..HealthResultEntry::new(
+                checker,
+                resp.health(),
+                resp.output().to_owned(),
+                resp.action().map(str::to_string),
+                resp.impact().map(str::to_string),
+            )
+        }
+    }
+    // Boxed, as groups run their checkers recursively
+    .boxed()
+}
 
-    pub(crate) fn to_json(&self) -> Value {
-        json!({
-            "name": self.name,
-            "slack": self.slack,
-        })
+async fn run_single(
+    checker: &NamedChecker,
+    group: &str,
+    default_timeout: Duration,
+    metrics: &CheckMetrics,
+) -> CheckResponse {
+    let timeout = checker.timeout().unwrap_or(default_timeout);
+    let start = Instant::now();
+
+    // A panicking checker must not take down the request serving the check
+    let check = AssertUnwindSafe(checker.check()).catch_unwind();
+
+    match future::select(check, Delay::new(timeout)).await {
+        Either::Left((Ok(resp), _)) => resp,
+        Either::Left((Err(panic), _)) => {
+            metrics.panicked(checker, group);
+
+            CheckResponse::unhealthy(
+                &format!("check panicked: {}", panic_message(&panic)),
+                "Investigate the panic in the check",
+                "The health of this dependency is unknown",
+            )
+        }
+        Either::Right(_) => timed_out(start),
     }
 }
 
-struct Link {
-    description: String,
-    url: String,
+/// Runs the checkers of a group, limited by the timeout of the group if it has one.
+async fn run_group(
+    checkers: &Group,
+    timeout: Option<Duration>,
+    parent: &str,
+    default_timeout: Duration,
+    metrics: &CheckMetrics,
+) -> (CheckResponse, Vec<HealthResultEntry>) {
+    let path = metrics::group_path(parent, checkers.name());
+    let start = Instant::now();
+
+    let checks = future::join_all(
+        checkers
+            .checkers()
+            .iter()
+            .map(|c| run_checker(c, &path, default_timeout, metrics)),
+    );
+
+    let checks = match timeout {
+        Some(timeout) => match future::select(checks, Delay::new(timeout)).await {
+            Either::Left((checks, _)) => checks,
+            Either::Right(_) => return (timed_out(start), Vec::new()),
+        },
+        None => checks.await,
+    };
+
+    let results = checkers
+        .checkers()
+        .iter()
+        .zip(&checks)
+        .map(|(c, entry)| (c, entry.health, entry.output.as_str()))
+        .collect::<Vec<_>>();
+
+    (checkers.combine(&results), checks)
+}
+
+/// Runs a group outside of a status, as the checker of the group.
+///
+/// The checkers are run as a status would, but their metrics are registered nowhere.
+pub(crate) async fn check_group(checkers: &Group, timeout: Option<Duration>) -> CheckResponse {
+    let metrics = CheckMetrics::detached();
+
+    run_group(checkers, timeout, "", DEFAULT_CHECK_TIMEOUT, &metrics)
+        .await
+        .0
 }
 
-impl Link {
-    fn new(description: &str, url: &str) -> Self {
-        Self {
-            description: description.to_owned(),
-            url: url.to_owned(),
+fn timed_out(start: Instant) -> CheckResponse {
+    CheckResponse::unhealthy(
+        &format!(
+            "check timed out after {:.3}s",
+            start.elapsed().as_secs_f64()
+        ),
+        "Investigate why the check is not completing in time",
+        "The health of this dependency is unknown",
+    )
+}
+
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Status;
+    use crate::{NamedChecker, StatusBuilder};
+
+    use ops_core::{async_trait, CheckResponse, Checker, Health};
+    use prometheus::Registry;
+
+    struct Panics;
+
+    #[async_trait]
+    impl Checker for Panics {
+        async fn check(&self) -> CheckResponse {
+            panic!("oops")
         }
     }
 
-    pub(crate) fn to_json(&self) -> Value {
-        json!({
-            "description": self.description,
-            "url": self.url,
-        })
+    /// The value of `healthcheck_panics_total` for the check in the group.
+    fn panics(registry: &Registry, name: &str, group: &str) -> Option<f64> {
+        registry
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == "healthcheck_panics_total")
+            .flat_map(|family| family.get_metric())
+            .find(|metric| {
+                let label = |label_name: &str| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .find(|label| label.get_name() == label_name)
+                        .map(|label| label.get_value().to_owned())
+                };
+
+                label("healthcheck_name").as_deref() == Some(name)
+                    && label("healthcheck_group").as_deref() == Some(group)
+            })
+            .map(|metric| metric.get_counter().get_value())
+    }
+
+    #[tokio::test]
+    async fn panics_are_counted() {
+        let registry = Registry::new();
+        let status = StatusBuilder::healthchecks("app", "")
+            .prometheus_registry(registry.clone())
+            .unwrap()
+            .checker(NamedChecker::new("panics", Box::new(Panics)))
+            .checker(NamedChecker::group(
+                "group",
+                vec![NamedChecker::new("member", Box::new(Panics))],
+            ));
+
+        assert_eq!(panics(&registry, "panics", ""), None);
+
+        let report = status.check().await.unwrap();
+        let entry = &report.checks()[0];
+
+        assert_eq!(entry.health(), Health::Unhealthy);
+        assert_eq!(entry.output(), "check panicked: oops");
+        assert_eq!(panics(&registry, "panics", ""), Some(1.0));
+        assert_eq!(panics(&registry, "member", "group"), Some(1.0));
+
+        status.check().await;
+
+        assert_eq!(panics(&registry, "panics", ""), Some(2.0));
+        assert_eq!(panics(&registry, "member", "group"), Some(2.0));
     }
 }
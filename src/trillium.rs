@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
+use crate::compression;
+use crate::exposition;
+use crate::health::group_param;
 use crate::status::Status;
-use crate::Result;
 
 use serde::Serialize;
-use trillium::{conn_try, conn_unwrap, Conn, Handler, KnownHeaderName::ContentType, State};
-use trillium_router::Router;
+use trillium::{conn_try, conn_unwrap, Conn, Handler, KnownHeaderName, State};
+use trillium_router::{Router, RouterConnExt};
 
 /// Routes to be attached to a Trillium app runtime
 pub fn router<S: Status + 'static>(status: S) -> impl Handler {
@@ -17,18 +19,44 @@ pub fn router<S: Status + 'static>(status: S) -> impl Handler {
 fn routes<S: Status + 'static>() -> impl Handler {
     Router::new()
         .get("/about", about::<S>)
-        .get("/metrics", metrics)
+        .get("/metrics", metrics::<S>)
         .get("/ready", ready::<S>)
+        .get("/live", live::<S>)
+        .get("/startup", startup::<S>)
         .get("/health", health::<S>)
+        .get("/health/:name", run_check::<S>)
 }
 
 async fn ready<S: Status + 'static>(conn: Conn) -> Conn {
     let status = conn_unwrap!(conn.state::<Arc<S>>(), conn);
 
-    match status.ready().await {
-        Some(is_ready) => {
-            if is_ready {
-                conn.with_status(200).with_body("ready\n")
+    let is_ready = status.ready().await;
+
+    probe(conn, is_ready, "ready\n")
+}
+
+async fn live<S: Status + 'static>(conn: Conn) -> Conn {
+    let status = conn_unwrap!(conn.state::<Arc<S>>(), conn);
+
+    let is_live = status.live().await;
+
+    probe(conn, is_live, "live\n")
+}
+
+async fn startup<S: Status + 'static>(conn: Conn) -> Conn {
+    let status = conn_unwrap!(conn.state::<Arc<S>>(), conn);
+
+    let is_started = status.started().await;
+
+    probe(conn, is_started, "started\n")
+}
+
+/// Responds to a probe, with 404 if the status does not support it.
+fn probe(conn: Conn, state: Option<bool>, ok: &'static str) -> Conn {
+    match state {
+        Some(is_ok) => {
+            if is_ok {
+                conn.with_status(200).with_body(ok)
             } else {
                 conn.with_status(503).with_body("Service unavailable")
             }
@@ -38,14 +66,38 @@ async fn ready<S: Status + 'static>(conn: Conn) -> Conn {
 }
 
 async fn health<S: Status + 'static>(conn: Conn) -> Conn {
-    let status = conn_unwrap!(conn.state::<Arc<S>>(), conn);
+    let status = conn_unwrap!(conn.state::<Arc<S>>(), conn).clone();
+
+    let group = group_param(Some(conn.querystring()));
+
+    let report = match group {
+        Some(group) => status.check_group(group).await,
+        None => status.check().await,
+    };
+
+    match report {
+        Some(resp) => {
+            let body = conn_try!(serde_json::to_vec(&resp), conn);
 
-    match status.check().await {
-        Some(resp) => conn.with_status(200).with_json(resp.to_json()),
+            let status_code = status.health_status_code(resp.health());
+
+            compressed(conn, status.as_ref(), status_code, "application/json", body)
+        }
+        None if group.is_some() => conn.with_status(404).with_body("No such group"),
         None => conn.with_status(404).with_body("No health checks"),
     }
 }
 
+async fn run_check<S: Status + 'static>(conn: Conn) -> Conn {
+    let status = conn_unwrap!(conn.state::<Arc<S>>(), conn).clone();
+    let name = conn_unwrap!(conn.param("name"), conn).to_owned();
+
+    match status.run_check(&name).await {
+        Some(entry) => conn.with_status(200).with_json(entry),
+        None => conn.with_status(404).with_body("No such check"),
+    }
+}
+
 async fn about<S: Status + 'static>(conn: Conn) -> Conn {
     let status = conn_unwrap!(conn.state::<Arc<S>>(), conn);
 
@@ -54,12 +106,50 @@ async fn about<S: Status + 'static>(conn: Conn) -> Conn {
     conn.with_status(200).with_json(about)
 }
 
-async fn metrics(conn: Conn) -> Conn {
-    let metrics = conn_try!(render_metrics(), conn);
+async fn metrics<S: Status + 'static>(conn: Conn) -> Conn {
+    let status = conn_unwrap!(conn.state::<Arc<S>>(), conn).clone();
+
+    let accept = conn.request_headers().get_str(KnownHeaderName::Accept);
+
+    let rendered = conn_try!(exposition::render(&status.gather(), accept), conn);
 
-    conn.with_status(200)
-        .with_header(ContentType, "text/plain; version=0.0.4; charset=utf-8")
-        .with_body(metrics)
+    compressed(
+        conn,
+        status.as_ref(),
+        200,
+        rendered.content_type,
+        rendered.body,
+    )
+}
+
+/// Responds with the body, compressed if the client accepts it and it is large enough.
+fn compressed<S: Status>(
+    conn: Conn,
+    status: &S,
+    status_code: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+) -> Conn {
+    let accept_encoding = conn
+        .request_headers()
+        .get_str(KnownHeaderName::AcceptEncoding);
+
+    let compressed = conn_try!(
+        compression::compress(body, accept_encoding, status.compression_min_size()),
+        conn
+    );
+
+    let conn = conn
+        .with_status(status_code)
+        .with_response_header(KnownHeaderName::ContentType, content_type)
+        .with_response_header(KnownHeaderName::Vary, "Accept-Encoding");
+
+    match compressed.encoding.content_encoding() {
+        Some(encoding) => conn
+            .with_response_header(KnownHeaderName::ContentEncoding, encoding)
+            .with_body(compressed.body),
+        None => conn.with_body(compressed.body),
+    }
 }
 
 trait JsonConnExt {
@@ -69,19 +159,7 @@ trait JsonConnExt {
 impl JsonConnExt for Conn {
     fn with_json(self, t: impl Serialize + Send + Sync + 'static) -> Self {
         let body = conn_try!(serde_json::to_string(&t), self);
-        self.with_header(ContentType, "application/json")
+        self.with_header(KnownHeaderName::ContentType, "application/json")
             .with_body(body)
     }
 }
-
-fn render_metrics() -> Result<Vec<u8>> {
-    use prometheus::{gather, Encoder, TextEncoder};
-
-    let metric_family = gather();
-
-    let mut writer = Vec::<u8>::new();
-    let encoder = TextEncoder::new();
-    encoder.encode(&metric_family, &mut writer)?;
-
-    Ok(writer)
-}
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::http_checker::{get, parse_http_uri};
+use crate::report::HealthReport;
+use crate::Result;
+
+use hyper::client::HttpConnector;
+use hyper::{Client, Uri};
+use ops_core::{async_trait, CheckResponse, Checker, Health};
+
+/// Checks the health of a downstream service that exposes the ops endpoints.
+///
+/// The overall health of the downstream `/__/health` becomes the health of this check.
+pub struct UpstreamChecker {
+    client: Client<HttpConnector>,
+    uri: Uri,
+    timeout: Duration,
+    action: String,
+    impact: String,
+}
+
+impl fmt::Debug for UpstreamChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpstreamChecker")
+            .field("uri", &self.uri)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl UpstreamChecker {
+    /// Creates an [`UpstreamChecker`](struct.UpstreamChecker.html) for the base URL of a service,
+    /// such as `http://service:8081`, with a timeout of 5 seconds.
+    ///
+    /// Fails unless the base URL is a plain `http` URL.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let uri = parse_http_uri(&format!("{}/__/health", base_url.trim_end_matches('/')))?;
+
+        Ok(UpstreamChecker {
+            client: Client::new(),
+            uri,
+            timeout: Duration::from_secs(5),
+            action: format!("Check the health of {}", base_url),
+            impact: format!("{} is not working as expected", base_url),
+        })
+    }
+
+    /// Sets the timeout for fetching the downstream health.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the action reported when the check is not healthy.
+    pub fn action(mut self, action: &str) -> Self {
+        self.action = action.to_owned();
+        self
+    }
+
+    /// Sets the impact reported when the check is unhealthy.
+    pub fn impact(mut self, impact: &str) -> Self {
+        self.impact = impact.to_owned();
+        self
+    }
+}
+
+#[async_trait]
+impl Checker for UpstreamChecker {
+    async fn check(&self) -> CheckResponse {
+        let (status, body, _) = match get(&self.client, &self.uri, self.timeout).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                return CheckResponse::unhealthy(
+                    &format!("GET {} failed: {}", self.uri, err),
+                    &self.action,
+                    &self.impact,
+                )
+            }
+        };
+
+        // The body is parsed whatever the status, as unhealthy services may return an error status
+        let report = match serde_json::from_slice::<HealthReport>(&body) {
+            Ok(report) => report,
+            Err(_) => {
+                return CheckResponse::unhealthy(
+                    &format!(
+                        "GET {} returned {} without a health report",
+                        self.uri, status
+                    ),
+                    &self.action,
+                    &self.impact,
+                )
+            }
+        };
+
+        let output = output(&report);
+
+        match report.health() {
+            Health::Healthy => CheckResponse::healthy(&output),
+            Health::Degraded => CheckResponse::degraded(&output, &self.action),
+            Health::Unhealthy => CheckResponse::unhealthy(&output, &self.action, &self.impact),
+        }
+    }
+}
+
+/// Describes the downstream health, naming the checks that are not healthy.
+fn output(report: &HealthReport) -> String {
+    let health: &'static str = report.health().into();
+
+    let failed = report
+        .checks()
+        .iter()
+        .filter(|check| check.health() != Health::Healthy)
+        .map(|check| {
+            let health: &'static str = check.health().into();
+            format!("{} is {}: {}", check.name(), health, check.output())
+        })
+        .collect::<Vec<_>>();
+
+    if failed.is_empty() {
+        format!("{} is {}", report.name(), health)
+    } else {
+        format!("{} is {}, {}", report.name(), health, failed.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::UpstreamChecker;
+    use crate::http_checker::tests::serve;
+    use crate::Error;
+
+    use ops_core::{Checker, Health};
+
+    #[test]
+    fn only_http_urls_are_supported() {
+        match UpstreamChecker::new("https://service:8081/") {
+            Err(Error::UnsupportedScheme(url)) => assert_eq!(url, "https://service:8081/__/health"),
+            other => panic!("https was not rejected: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn healthy_report() {
+        let url = serve(
+            200,
+            r#"{"name":"svc","description":"","health":"healthy","checks":[]}"#,
+            Duration::ZERO,
+        );
+
+        let resp = UpstreamChecker::new(&url).unwrap().check().await;
+
+        assert_eq!(resp.health(), Health::Healthy);
+        assert_eq!(resp.output(), "svc is healthy");
+    }
+
+    #[tokio::test]
+    async fn unhealthy_report_with_error_status() {
+        let url = serve(
+            503,
+            r#"{"name":"svc","description":"","health":"unhealthy","checks":[
+                {"name":"db","health":"unhealthy","output":"connection refused"},
+                {"name":"cache","health":"degraded","output":"slow"},
+                {"name":"queue","health":"healthy","output":"ok"}
+            ]}"#,
+            Duration::ZERO,
+        );
+
+        let resp = UpstreamChecker::new(&url).unwrap().check().await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert_eq!(
+            resp.output(),
+            "svc is unhealthy, db is unhealthy: connection refused; cache is degraded: slow"
+        );
+    }
+
+    #[tokio::test]
+    async fn degraded_report() {
+        let url = serve(
+            200,
+            r#"{"name":"svc","description":"","health":"degraded"}"#,
+            Duration::ZERO,
+        );
+
+        let resp = UpstreamChecker::new(&url).unwrap().check().await;
+
+        assert_eq!(resp.health(), Health::Degraded);
+        assert_eq!(resp.output(), "svc is degraded");
+    }
+
+    #[tokio::test]
+    async fn missing_report_is_unhealthy() {
+        let url = serve(502, "Bad Gateway", Duration::ZERO);
+
+        let resp = UpstreamChecker::new(&format!("{}/", url))
+            .unwrap()
+            .check()
+            .await;
+
+        assert_eq!(resp.health(), Health::Unhealthy);
+        assert_eq!(
+            resp.output(),
+            format!(
+                "GET {}/__/health returned 502 Bad Gateway without a health report",
+                url
+            )
+        );
+    }
+}